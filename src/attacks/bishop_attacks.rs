@@ -23,9 +23,31 @@ impl BishopAttacks {
 
         BISHOP_ATTACKS[square][index]
     }
+
+    //Checks the provided bishop attack generator against ray walking for every occupancy subset of
+    //every relevant mask, both with empty and with fully occupied edges.
+    pub fn verify<F: Fn(Square, Bitboard) -> Bitboard>(method: F) -> bool {
+        for square_index in 0..64 {
+            let square = Square::from_raw(square_index);
+            let attack_mask = BISHOP_MASKS[square_index as usize];
+            let edges = !attack_mask & !square.get_bit();
+            let relevant_bit_count = attack_mask.pop_count();
+            for index in 0..1usize << relevant_bit_count {
+                let occupancy = generate_occupancy(index, relevant_bit_count as usize, attack_mask);
+                if method(square, occupancy) != generate_bishop_attacks(square, occupancy)
+                    || method(square, occupancy | edges)
+                        != generate_bishop_attacks(square, occupancy | edges)
+                {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
 }
 
-const BISHOP_MASKS: [Bitboard; 64] = {
+pub(super) const BISHOP_MASKS: [Bitboard; 64] = {
     let mut result = [Bitboard::EMPTY; 64];
     let mut square_index = 0u8;
     while square_index < 64 {
@@ -131,7 +153,7 @@ const fn mask_bishop_attacks(square: Square) -> Bitboard {
     Bitboard::from_raw(result)
 }
 
-fn generate_bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+pub(super) fn generate_bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
     let mut result: Bitboard = Bitboard::EMPTY;
     let bishop_position = (square.get_rank() as i32, square.get_file() as i32);
 
@@ -182,7 +204,11 @@ fn generate_bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
     result
 }
 
-fn generate_occupancy(index: usize, bit_count: usize, attack_mask: Bitboard) -> Bitboard {
+pub(super) fn generate_occupancy(
+    index: usize,
+    bit_count: usize,
+    attack_mask: Bitboard,
+) -> Bitboard {
     let mut result = Bitboard::EMPTY;
     let mut mut_attack_mask = attack_mask;
     let mut count_index = 0u16;
@@ -198,8 +224,7 @@ fn generate_occupancy(index: usize, bit_count: usize, attack_mask: Bitboard) ->
     result
 }
 
-#[cfg(not(target_feature = "bmi2"))]
-const MAGIC_NUMBERS_BISHOP: [u64; 64] = [
+pub(super) const MAGIC_NUMBERS_BISHOP: [u64; 64] = [
     9300092178686681120,
     1284830893973760,
     2322997520105472,
@@ -0,0 +1,62 @@
+use crate::{Bitboard, Square};
+
+//Table-free slider attacks based on hyperbola quintessence.
+//Files and diagonals are reversed with a byte swap, ranks with a bit reversal of the rank byte.
+pub struct HyperbolaAttacks;
+impl HyperbolaAttacks {
+    #[inline]
+    pub fn get_bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+        line_attacks(square, occupancy, diagonal_mask(square))
+            | line_attacks(square, occupancy, anti_diagonal_mask(square))
+    }
+
+    #[inline]
+    pub fn get_rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+        line_attacks(square, occupancy, file_mask(square)) | rank_attacks(square, occupancy)
+    }
+}
+
+#[inline]
+fn line_attacks(square: Square, occupancy: Bitboard, mask: u64) -> Bitboard {
+    let slider = square.get_bit().get_raw();
+    let mut forward = occupancy.get_raw() & mask;
+    let mut reverse = forward.swap_bytes();
+    forward = forward.wrapping_sub(slider);
+    reverse = reverse.wrapping_sub(slider.swap_bytes());
+    Bitboard::from_raw((forward ^ reverse.swap_bytes()) & mask)
+}
+
+#[inline]
+fn rank_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    let shift = square.get_rank() as u32 * 8;
+    let slider = 1u8 << square.get_file();
+    let mask = !slider;
+    let mut forward = (occupancy.get_raw() >> shift) as u8 & mask;
+    let mut reverse = forward.reverse_bits();
+    forward = forward.wrapping_sub(slider);
+    reverse = reverse.wrapping_sub(slider.reverse_bits());
+    Bitboard::from_raw((((forward ^ reverse.reverse_bits()) & mask) as u64) << shift)
+}
+
+#[inline]
+const fn file_mask(square: Square) -> u64 {
+    (Bitboard::FILE_A.get_raw() << square.get_file()) & !square.get_bit().get_raw()
+}
+
+#[inline]
+const fn diagonal_mask(square: Square) -> u64 {
+    let square_index = square.get_raw() as i32;
+    let diagonal = 8 * (square_index & 7) - (square_index & 56);
+    let north = -diagonal & (diagonal >> 31);
+    let south = diagonal & (-diagonal >> 31);
+    ((0x8040201008040201u64 >> south) << north) & !square.get_bit().get_raw()
+}
+
+#[inline]
+const fn anti_diagonal_mask(square: Square) -> u64 {
+    let square_index = square.get_raw() as i32;
+    let diagonal = 56 - 8 * (square_index & 7) - (square_index & 56);
+    let north = -diagonal & (diagonal >> 31);
+    let south = diagonal & (-diagonal >> 31);
+    ((0x0102040810204080u64 >> south) << north) & !square.get_bit().get_raw()
+}
@@ -0,0 +1,319 @@
+use crate::{utils::Random, Bitboard, Square};
+
+use super::{
+    bishop_attacks::{
+        generate_bishop_attacks, generate_occupancy, BishopAttacks, BISHOP_MASKS,
+        MAGIC_NUMBERS_BISHOP,
+    },
+    rook_attacks::{generate_rook_attacks, RookAttacks, MAGIC_NUMBERS_ROOK, ROOK_MASKS},
+};
+
+//Index bits are the number of bits left after the magic shift (64 - shift), from 1 to 12.
+//Using fewer bits than the mask population relies on constructive collisions between
+//occupancies that share the same attacks. Such magics need tables built for their own shift,
+//the shipped 512 and 4096 slot tables always shift by the full mask population, so only magics
+//found with `mask bits` index bits can replace MAGIC_NUMBERS_BISHOP and MAGIC_NUMBERS_ROOK.
+pub struct MagicGenerator;
+impl MagicGenerator {
+    pub fn get_bishop_mask(square: Square) -> Bitboard {
+        BISHOP_MASKS[usize::from(square)]
+    }
+
+    pub fn get_rook_mask(square: Square) -> Bitboard {
+        ROOK_MASKS[usize::from(square)]
+    }
+
+    pub fn find_bishop_magic(
+        square: Square,
+        index_bits: u32,
+        random: &mut Random,
+        max_attempts: u32,
+    ) -> Result<Option<u64>, String> {
+        find_magic(
+            &SliderSubsets::new(
+                square,
+                BISHOP_MASKS[usize::from(square)],
+                generate_bishop_attacks,
+            ),
+            index_bits,
+            random,
+            max_attempts,
+        )
+    }
+
+    pub fn find_rook_magic(
+        square: Square,
+        index_bits: u32,
+        random: &mut Random,
+        max_attempts: u32,
+    ) -> Result<Option<u64>, String> {
+        find_magic(
+            &SliderSubsets::new(
+                square,
+                ROOK_MASKS[usize::from(square)],
+                generate_rook_attacks,
+            ),
+            index_bits,
+            random,
+            max_attempts,
+        )
+    }
+
+    //Searches magics for every square with `mask bits - bit_reduction` index bits.
+    //Squares for which no magic was found within `max_attempts` are left as None.
+    pub fn generate_bishop_magics(
+        seed: u64,
+        bit_reduction: u32,
+        max_attempts: u32,
+    ) -> [Option<u64>; 64] {
+        let mut random = Random::from_seed(seed);
+        let mut result = [None; 64];
+        for (square_index, magic) in result.iter_mut().enumerate() {
+            let square = Square::from_raw(square_index as u8);
+            let index_bits = reduced_index_bits(BISHOP_MASKS[square_index], bit_reduction);
+            *magic = Self::find_bishop_magic(square, index_bits, &mut random, max_attempts)
+                .unwrap_or_default();
+        }
+        result
+    }
+
+    pub fn generate_rook_magics(
+        seed: u64,
+        bit_reduction: u32,
+        max_attempts: u32,
+    ) -> [Option<u64>; 64] {
+        let mut random = Random::from_seed(seed);
+        let mut result = [None; 64];
+        for (square_index, magic) in result.iter_mut().enumerate() {
+            let square = Square::from_raw(square_index as u8);
+            let index_bits = reduced_index_bits(ROOK_MASKS[square_index], bit_reduction);
+            *magic = Self::find_rook_magic(square, index_bits, &mut random, max_attempts)
+                .unwrap_or_default();
+        }
+        result
+    }
+
+    //Checks that every occupancy subset of the bishop mask lands in a slot holding its own ray
+    //attacks, i.e. the magic has no destructive collisions.
+    pub fn verify_bishop_magic(square: Square, magic: u64, index_bits: u32) -> bool {
+        if validate_index_bits(index_bits).is_err() {
+            return false;
+        }
+
+        let subsets = SliderSubsets::new(
+            square,
+            BISHOP_MASKS[usize::from(square)],
+            generate_bishop_attacks,
+        );
+        subsets.maps_without_collisions(magic, index_bits, &mut MagicTable::new(index_bits))
+    }
+
+    pub fn verify_rook_magic(square: Square, magic: u64, index_bits: u32) -> bool {
+        if validate_index_bits(index_bits).is_err() {
+            return false;
+        }
+
+        let subsets = SliderSubsets::new(
+            square,
+            ROOK_MASKS[usize::from(square)],
+            generate_rook_attacks,
+        );
+        subsets.maps_without_collisions(magic, index_bits, &mut MagicTable::new(index_bits))
+    }
+
+    //Verifies MAGIC_NUMBERS_BISHOP and MAGIC_NUMBERS_ROOK with full mask sized indices, together
+    //with the attack tables used by the compiled backend.
+    pub fn verify_shipped_magics() -> bool {
+        for square_index in 0..64 {
+            let square = Square::from_raw(square_index as u8);
+            if !Self::verify_bishop_magic(
+                square,
+                MAGIC_NUMBERS_BISHOP[square_index],
+                BISHOP_MASKS[square_index].pop_count(),
+            ) || !Self::verify_rook_magic(
+                square,
+                MAGIC_NUMBERS_ROOK[square_index],
+                ROOK_MASKS[square_index].pop_count(),
+            ) {
+                return false;
+            }
+        }
+
+        BishopAttacks::verify(BishopAttacks::get_bishop_attacks)
+            && RookAttacks::verify(RookAttacks::get_rook_attacks)
+    }
+}
+
+fn reduced_index_bits(mask: Bitboard, bit_reduction: u32) -> u32 {
+    mask.pop_count().saturating_sub(bit_reduction).max(1)
+}
+
+fn validate_index_bits(index_bits: u32) -> Result<(), String> {
+    if !(1..=12).contains(&index_bits) {
+        return Err(format!(
+            "index bits have to be between 1 and 12, got {index_bits}"
+        ));
+    }
+    Ok(())
+}
+
+//None when no magic was found within max_attempts
+fn find_magic(
+    subsets: &SliderSubsets,
+    index_bits: u32,
+    random: &mut Random,
+    max_attempts: u32,
+) -> Result<Option<u64>, String> {
+    validate_index_bits(index_bits)?;
+
+    let mut table = MagicTable::new(index_bits);
+    for _ in 0..max_attempts {
+        let magic = random.next_sparse_u64();
+        if (subsets.mask.get_raw().wrapping_mul(magic) & 0xFF00000000000000).count_ones() < 6 {
+            continue;
+        }
+
+        if subsets.maps_without_collisions(magic, index_bits, &mut table) {
+            return Ok(Some(magic));
+        }
+    }
+
+    Ok(None)
+}
+
+struct SliderSubsets {
+    mask: Bitboard,
+    occupancies: Vec<Bitboard>,
+    attacks: Vec<Bitboard>,
+}
+
+impl SliderSubsets {
+    fn new(square: Square, mask: Bitboard, generator: fn(Square, Bitboard) -> Bitboard) -> Self {
+        let bit_count = mask.pop_count() as usize;
+        let occupancies: Vec<Bitboard> = (0..1usize << bit_count)
+            .map(|index| generate_occupancy(index, bit_count, mask))
+            .collect();
+        let attacks = occupancies
+            .iter()
+            .map(|&occupancy| generator(square, occupancy))
+            .collect();
+
+        Self {
+            mask,
+            occupancies,
+            attacks,
+        }
+    }
+
+    fn maps_without_collisions(&self, magic: u64, index_bits: u32, table: &mut MagicTable) -> bool {
+        table.next_epoch();
+        for (occupancy, attacks) in self.occupancies.iter().zip(&self.attacks) {
+            let index = (occupancy.get_raw().wrapping_mul(magic) >> (64 - index_bits)) as usize;
+            if !table.insert(index, *attacks) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+//Attack table that is cleared in O(1) between attempts by tagging every slot with the attempt
+//it was written in.
+struct MagicTable {
+    attacks: Vec<Bitboard>,
+    epochs: Vec<u32>,
+    epoch: u32,
+}
+
+impl MagicTable {
+    fn new(index_bits: u32) -> Self {
+        Self {
+            attacks: vec![Bitboard::EMPTY; 1 << index_bits],
+            epochs: vec![0; 1 << index_bits],
+            epoch: 0,
+        }
+    }
+
+    fn next_epoch(&mut self) {
+        self.epoch += 1;
+    }
+
+    fn insert(&mut self, index: usize, attacks: Bitboard) -> bool {
+        if self.epochs[index] != self.epoch {
+            self.epochs[index] = self.epoch;
+            self.attacks[index] = attacks;
+            return true;
+        }
+
+        self.attacks[index] == attacks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MagicGenerator;
+    use crate::{utils::Random, Square};
+
+    #[test]
+    fn shipped_magics_are_valid() {
+        assert!(MagicGenerator::verify_shipped_magics());
+    }
+
+    #[test]
+    fn found_magics_verify() {
+        for square in [Square::A1, Square::E4, Square::H8] {
+            let bishop_bits = MagicGenerator::get_bishop_mask(square).pop_count();
+            let rook_bits = MagicGenerator::get_rook_mask(square).pop_count();
+
+            let mut random = Random::from_seed(27);
+            let bishop =
+                MagicGenerator::find_bishop_magic(square, bishop_bits, &mut random, 1_000_000);
+            let bishop = bishop.unwrap().unwrap();
+            assert!(MagicGenerator::verify_bishop_magic(
+                square,
+                bishop,
+                bishop_bits
+            ));
+
+            let rook = MagicGenerator::find_rook_magic(square, rook_bits, &mut random, 1_000_000);
+            let rook = rook.unwrap().unwrap();
+            assert!(MagicGenerator::verify_rook_magic(square, rook, rook_bits));
+            assert!(!MagicGenerator::verify_rook_magic(square, 0, rook_bits));
+        }
+
+        let magics = MagicGenerator::generate_bishop_magics(27, 0, 1_000_000);
+        for (square_index, magic) in magics.iter().enumerate() {
+            let square = Square::from_raw(square_index as u8);
+            let index_bits = MagicGenerator::get_bishop_mask(square).pop_count();
+            assert!(MagicGenerator::verify_bishop_magic(
+                square,
+                magic.unwrap(),
+                index_bits
+            ));
+        }
+        assert!(magics == MagicGenerator::generate_bishop_magics(27, 0, 1_000_000));
+    }
+
+    #[test]
+    fn rejects_out_of_range_index_bits() {
+        let mut random = Random::from_seed(27);
+        for index_bits in [0, 13, 64] {
+            assert!(
+                MagicGenerator::find_bishop_magic(Square::E4, index_bits, &mut random, 1).is_err()
+            );
+            assert!(
+                MagicGenerator::find_rook_magic(Square::E4, index_bits, &mut random, 1).is_err()
+            );
+            assert!(!MagicGenerator::verify_rook_magic(
+                Square::E4,
+                u64::MAX,
+                index_bits
+            ));
+        }
+
+        //Exhausting the attempts is not an error
+        let result = MagicGenerator::find_rook_magic(Square::A1, 1, &mut random, 10);
+        assert!(result == Ok(None));
+    }
+}
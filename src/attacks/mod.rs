@@ -1,10 +1,13 @@
 mod bishop_attacks;
+mod hyperbola_attacks;
 mod king_attacks;
 mod knight_attacks;
+mod magic_generator;
 mod pawn_attacks;
 mod piece_attacks;
 mod rays;
 mod rook_attacks;
 
+pub use magic_generator::MagicGenerator;
 pub use piece_attacks::Attacks;
 pub use rays::Rays;
@@ -1,12 +1,19 @@
 use crate::{Bitboard, ChessBoard, Piece, Square};
 
 use super::{
-    bishop_attacks::BishopAttacks, king_attacks::KingAttacks, knight_attacks::KnightAttacks,
-    pawn_attacks::PawnsAttacks, rook_attacks::RookAttacks,
+    bishop_attacks::BishopAttacks, hyperbola_attacks::HyperbolaAttacks, king_attacks::KingAttacks,
+    knight_attacks::KnightAttacks, pawn_attacks::PawnsAttacks, rook_attacks::RookAttacks,
 };
 
 pub struct Attacks;
 impl Attacks {
+    #[cfg(feature = "table-free")]
+    pub const SLIDER_BACKEND: &'static str = "Hyperbola";
+    #[cfg(all(not(feature = "table-free"), target_feature = "bmi2"))]
+    pub const SLIDER_BACKEND: &'static str = "PEXT";
+    #[cfg(all(not(feature = "table-free"), not(target_feature = "bmi2")))]
+    pub const SLIDER_BACKEND: &'static str = "Magic";
+
     #[inline]
     pub fn get_king_attacks_for_square(square: Square) -> Bitboard {
         KingAttacks::ATTACK_TABLE[square.get_raw() as usize]
@@ -24,12 +31,34 @@ impl Attacks {
 
     #[inline]
     pub fn get_bishop_attacks_for_square(square: Square, occupancy: Bitboard) -> Bitboard {
-        BishopAttacks::get_bishop_attacks(square, occupancy)
+        #[cfg(not(feature = "table-free"))]
+        let attacks = BishopAttacks::get_bishop_attacks(square, occupancy);
+
+        #[cfg(feature = "table-free")]
+        let attacks = HyperbolaAttacks::get_bishop_attacks(square, occupancy);
+
+        attacks
     }
 
     #[inline]
     pub fn get_rook_attacks_for_square(square: Square, occupancy: Bitboard) -> Bitboard {
-        RookAttacks::get_rook_attacks(square, occupancy)
+        #[cfg(not(feature = "table-free"))]
+        let attacks = RookAttacks::get_rook_attacks(square, occupancy);
+
+        #[cfg(feature = "table-free")]
+        let attacks = HyperbolaAttacks::get_rook_attacks(square, occupancy);
+
+        attacks
+    }
+
+    //Verifies that the table backend compiled for this target (magic or PEXT) and the table-free
+    //backend produce identical slider attacks, which makes perft results identical across them.
+    //Calling it in a table-free build allocates the slider tables.
+    pub fn verify_slider_backends() -> bool {
+        BishopAttacks::verify(BishopAttacks::get_bishop_attacks)
+            && BishopAttacks::verify(HyperbolaAttacks::get_bishop_attacks)
+            && RookAttacks::verify(RookAttacks::get_rook_attacks)
+            && RookAttacks::verify(HyperbolaAttacks::get_rook_attacks)
     }
 }
 
@@ -79,3 +108,248 @@ impl ChessBoard {
         attack_map.get_bit(square)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            bishop_attacks::{generate_bishop_attacks, BishopAttacks},
+            hyperbola_attacks::HyperbolaAttacks,
+            rook_attacks::{generate_rook_attacks, RookAttacks},
+        },
+        Attacks,
+    };
+    use crate::{
+        Bitboard, CastleRights, ChessBoard, Move, MoveFlag, Perft, Piece, Side, Square, FEN,
+    };
+
+    type SliderAttacks = fn(Square, Bitboard) -> Bitboard;
+
+    //(name, bishop attacks, rook attacks), the table backend is magic or PEXT depending on the target
+    const BACKENDS: [(&str, SliderAttacks, SliderAttacks); 3] = [
+        (
+            "table",
+            BishopAttacks::get_bishop_attacks,
+            RookAttacks::get_rook_attacks,
+        ),
+        (
+            "hyperbola",
+            HyperbolaAttacks::get_bishop_attacks,
+            HyperbolaAttacks::get_rook_attacks,
+        ),
+        (
+            "ray walking",
+            generate_bishop_attacks,
+            generate_rook_attacks,
+        ),
+    ];
+
+    const PERFT_POSITIONS: [(&str, u8, u128); 4] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            3,
+            8902,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+            97862,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+        (
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            3,
+            9467,
+        ),
+    ];
+
+    //Perft on a small pseudo-legal generator that takes every slider attack from the given
+    //functions, so the node count only depends on the backend and not on the compiled MoveGen
+    fn perft(board: &ChessBoard, depth: u8, bishop: SliderAttacks, rook: SliderAttacks) -> u128 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let mover = board.side_to_move();
+        let mut nodes = 0;
+        for mv in pseudo_legal_moves(board, bishop, rook) {
+            let mut board_copy = *board;
+            board_copy.apply_move(mv);
+            let king_square = (board_copy.get_piece_mask(Piece::KING)
+                & side_occupancy(&board_copy, mover))
+            .ls1b_square();
+            if !is_attacked(&board_copy, king_square, mover.flipped(), bishop, rook) {
+                nodes += perft(&board_copy, depth - 1, bishop, rook);
+            }
+        }
+        nodes
+    }
+
+    fn side_occupancy(board: &ChessBoard, side: Side) -> Bitboard {
+        if side == Side::WHITE {
+            board.get_occupancy_for_side::<true>()
+        } else {
+            board.get_occupancy_for_side::<false>()
+        }
+    }
+
+    fn is_attacked(
+        board: &ChessBoard,
+        square: Square,
+        attacker: Side,
+        bishop: SliderAttacks,
+        rook: SliderAttacks,
+    ) -> bool {
+        let occupancy = board.get_occupancy();
+        let queens = board.get_piece_mask(Piece::QUEEN);
+        let pawn_attacks = if attacker == Side::WHITE {
+            Attacks::get_pawn_attacks_for_square::<false>(square)
+        } else {
+            Attacks::get_pawn_attacks_for_square::<true>(square)
+        };
+        let attackers = (Attacks::get_knight_attacks_for_square(square)
+            & board.get_piece_mask(Piece::KNIGHT))
+            | (Attacks::get_king_attacks_for_square(square) & board.get_piece_mask(Piece::KING))
+            | (pawn_attacks & board.get_piece_mask(Piece::PAWN))
+            | (rook(square, occupancy) & (board.get_piece_mask(Piece::ROOK) | queens))
+            | (bishop(square, occupancy) & (board.get_piece_mask(Piece::BISHOP) | queens));
+        (attackers & side_occupancy(board, attacker)).is_not_empty()
+    }
+
+    fn pseudo_legal_moves(
+        board: &ChessBoard,
+        bishop: SliderAttacks,
+        rook: SliderAttacks,
+    ) -> Vec<Move> {
+        let side = board.side_to_move();
+        let own = side_occupancy(board, side);
+        let enemy = side_occupancy(board, side.flipped());
+        let occupancy = board.get_occupancy();
+        let mut moves = Vec::new();
+
+        own.map(|from| {
+            let targets = match board.get_piece_on_square(from) {
+                Piece::PAWN => return pawn_moves(board, from, enemy, &mut moves),
+                Piece::KNIGHT => Attacks::get_knight_attacks_for_square(from),
+                Piece::BISHOP => bishop(from, occupancy),
+                Piece::ROOK => rook(from, occupancy),
+                Piece::QUEEN => bishop(from, occupancy) | rook(from, occupancy),
+                _ => {
+                    castle_moves(board, bishop, rook, &mut moves);
+                    Attacks::get_king_attacks_for_square(from)
+                }
+            };
+            (targets & !own).map(|to| {
+                let flag = if enemy.get_bit(to) {
+                    MoveFlag::CAPTURE
+                } else {
+                    MoveFlag::QUIET_MOVE
+                };
+                moves.push(Move::from_squares(from, to, flag));
+            });
+        });
+        moves
+    }
+
+    fn pawn_moves(board: &ChessBoard, from: Square, enemy: Bitboard, moves: &mut Vec<Move>) {
+        let white = board.side_to_move() == Side::WHITE;
+        let (forward, start_rank, promotion_rank) = if white { (8, 1, 7) } else { (-8, 6, 0) };
+        let captures = if white {
+            Attacks::get_pawn_attacks_for_square::<true>(from)
+        } else {
+            Attacks::get_pawn_attacks_for_square::<false>(from)
+        };
+        let push = |moves: &mut Vec<Move>, to: Square, flag: u16| {
+            if to.get_rank() == promotion_rank {
+                for promotion in MoveFlag::KNIGHT_PROMOTION..=MoveFlag::QUEEN_PROMOTION {
+                    moves.push(Move::from_squares(from, to, promotion | flag));
+                }
+            } else {
+                moves.push(Move::from_squares(from, to, flag));
+            }
+        };
+
+        let single = Square::from_raw((from.get_raw() as i8 + forward) as u8);
+        if !board.get_occupancy().get_bit(single) {
+            push(moves, single, MoveFlag::QUIET_MOVE);
+            let double = Square::from_raw((single.get_raw() as i8 + forward) as u8);
+            if from.get_rank() == start_rank && !board.get_occupancy().get_bit(double) {
+                moves.push(Move::from_squares(from, double, MoveFlag::DOUBLE_PUSH));
+            }
+        }
+
+        (captures & enemy).map(|to| push(moves, to, MoveFlag::CAPTURE));
+        let en_passant = board.en_passant_square();
+        if en_passant != Square::NULL && captures.get_bit(en_passant) {
+            moves.push(Move::from_squares(from, en_passant, MoveFlag::EN_PASSANT));
+        }
+    }
+
+    fn castle_moves(
+        board: &ChessBoard,
+        bishop: SliderAttacks,
+        rook: SliderAttacks,
+        moves: &mut Vec<Move>,
+    ) {
+        let side = board.side_to_move();
+        let (king_right, queen_right, home) = if side == Side::WHITE {
+            (CastleRights::WHITE_KING, CastleRights::WHITE_QUEEN, 4)
+        } else {
+            (CastleRights::BLACK_KING, CastleRights::BLACK_QUEEN, 60)
+        };
+        let empty = |squares: &[u8]| {
+            squares
+                .iter()
+                .all(|&index| !board.get_occupancy().get_bit(Square::from_raw(index)))
+        };
+        let safe = |squares: &[u8]| {
+            squares.iter().all(|&index| {
+                !is_attacked(board, Square::from_raw(index), side.flipped(), bishop, rook)
+            })
+        };
+
+        let home_square = Square::from_raw(home);
+        if board.castle_rights().has_right(king_right)
+            && empty(&[home + 1, home + 2])
+            && safe(&[home, home + 1, home + 2])
+        {
+            moves.push(Move::from_squares(
+                home_square,
+                Square::from_raw(home + 2),
+                MoveFlag::KING_SIDE_CASTLE,
+            ));
+        }
+        if board.castle_rights().has_right(queen_right)
+            && empty(&[home - 1, home - 2, home - 3])
+            && safe(&[home, home - 1, home - 2])
+        {
+            moves.push(Move::from_squares(
+                home_square,
+                Square::from_raw(home - 2),
+                MoveFlag::QUEEN_SIDE_CASTLE,
+            ));
+        }
+    }
+
+    #[test]
+    fn slider_backends_match_ray_walking() {
+        assert!(Attacks::verify_slider_backends());
+    }
+
+    #[test]
+    fn slider_backends_have_identical_perft() {
+        for (fen, depth, expected) in PERFT_POSITIONS {
+            let fen = FEN::from_str(fen);
+            assert_eq!(Perft::perft::<false, false, false>(&fen, depth).0, expected);
+
+            let board = ChessBoard::from_fen(&fen);
+            for (name, bishop, rook) in BACKENDS {
+                assert_eq!(
+                    perft(&board, depth, bishop, rook),
+                    expected,
+                    "{name} on {fen}"
+                );
+            }
+        }
+    }
+}
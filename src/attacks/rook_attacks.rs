@@ -23,9 +23,31 @@ impl RookAttacks {
 
         ROOK_ATTACKS[square][index]
     }
+
+    //Checks the provided rook attack generator against ray walking for every occupancy subset of
+    //every relevant mask, both with empty and with fully occupied edges.
+    pub fn verify<F: Fn(Square, Bitboard) -> Bitboard>(method: F) -> bool {
+        for square_index in 0..64 {
+            let square = Square::from_raw(square_index);
+            let attack_mask = ROOK_MASKS[square_index as usize];
+            let edges = !attack_mask & !square.get_bit();
+            let relevant_bit_count = attack_mask.pop_count();
+            for index in 0..1usize << relevant_bit_count {
+                let occupancy = generate_occupancy(index, relevant_bit_count as usize, attack_mask);
+                if method(square, occupancy) != generate_rook_attacks(square, occupancy)
+                    || method(square, occupancy | edges)
+                        != generate_rook_attacks(square, occupancy | edges)
+                {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
 }
 
-const ROOK_MASKS: [Bitboard; 64] = {
+pub(super) const ROOK_MASKS: [Bitboard; 64] = {
     let mut result = [Bitboard::EMPTY; 64];
     let mut square_index = 0u8;
     while square_index < 64 {
@@ -126,7 +148,7 @@ const fn mask_rook_attacks(square: Square) -> Bitboard {
     Bitboard::from_raw(result)
 }
 
-fn generate_rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+pub(super) fn generate_rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
     let mut result: Bitboard = Bitboard::EMPTY;
     let rook_position = (square.get_rank() as i32, square.get_file() as i32);
 
@@ -189,8 +211,7 @@ fn generate_occupancy(index: usize, bit_count: usize, attack_mask: Bitboard) ->
     result
 }
 
-#[cfg(not(target_feature = "bmi2"))]
-const MAGIC_NUMBERS_ROOK: [u64; 64] = [
+pub(super) const MAGIC_NUMBERS_ROOK: [u64; 64] = [
     9259400973461241857,
     234187460333015040,
     36063981659521032,
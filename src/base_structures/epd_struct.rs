@@ -0,0 +1,442 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::{ChessBoard, Move};
+
+use super::FEN;
+
+//Extended Position Description: the first four FEN fields followed by operations,
+//each an opcode with operands terminated by a semicolon
+//r1b1k2r/pp3ppp/2n5/8/2B5/8/PPP2PPP/R1B1K2R w KQkq - bm Bb5+; id "test.001";
+#[derive(Clone, PartialEq, Default)]
+pub struct Epd {
+    pub fen: FEN,
+    operations: Vec<EpdOperation>,
+    //Counters followed the position fields directly and are written back as hmvc and fmvn
+    move_counters: bool,
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+impl Epd {
+    pub fn from_fen(fen: FEN) -> Self {
+        Self {
+            fen,
+            operations: Vec::new(),
+            move_counters: false,
+        }
+    }
+
+    //Parses an EPD line. Half and full move counters may follow the four position fields
+    //directly, as in most perft suites, or be given through the hmvc and fmvn operations.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(epd_string: &str) -> std::result::Result<Self, String> {
+        let epd_string = epd_string.trim();
+        let mut fields = Vec::new();
+        let mut rest = epd_string;
+        while fields.len() < 4 {
+            let trimmed = rest.trim_start();
+            let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+            if end == 0 {
+                break;
+            }
+            fields.push(&trimmed[..end]);
+            rest = &trimmed[end..];
+        }
+
+        let mut counters = ["0", "1"];
+        let mut move_counters = false;
+        for counter in &mut counters {
+            let trimmed = rest.trim_start();
+            let end = trimmed
+                .find(|character: char| character.is_whitespace() || character == ';')
+                .unwrap_or(trimmed.len());
+            let token = &trimmed[..end];
+            if token.is_empty() || token.parse::<u16>().is_err() {
+                break;
+            }
+            *counter = token;
+            move_counters = true;
+            rest = &trimmed[end..];
+        }
+
+        let fen_string = format!("{} {} {}", fields.join(" "), counters[0], counters[1]);
+        if fields.len() < 4 || !FEN::validate_fen(&fen_string) {
+            return Err(format!("invalid position '{}'", fields.join(" ")));
+        }
+
+        let mut result = Self::from_fen(FEN::from_string(fen_string));
+        result.operations = parse_operations(rest)?;
+        result.move_counters = move_counters;
+
+        if let Some(half_moves) = result.get_operand("hmvc") {
+            result.fen.half_move_counter = half_moves.to_string();
+        }
+        if let Some(full_moves) = result.get_operand("fmvn") {
+            result.fen.full_move_counter = full_moves.to_string();
+        }
+
+        Ok(result)
+    }
+
+    #[inline]
+    pub fn board(&self) -> ChessBoard {
+        ChessBoard::from_fen(&self.fen)
+    }
+
+    #[inline]
+    pub fn operations(&self) -> &[EpdOperation] {
+        &self.operations
+    }
+
+    pub fn get(&self, opcode: &str) -> Option<&EpdOperation> {
+        self.operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+    }
+
+    //First operand of the operation, if the operation is present
+    pub fn get_operand(&self, opcode: &str) -> Option<&str> {
+        self.get(opcode)
+            .and_then(|operation| operation.operands.first())
+            .map(String::as_str)
+    }
+
+    //Replaces the operation with the same opcode or appends a new one
+    pub fn set(&mut self, opcode: &str, operands: Vec<String>) {
+        match self
+            .operations
+            .iter_mut()
+            .find(|operation| operation.opcode == opcode)
+        {
+            Some(operation) => operation.operands = operands,
+            None => self.operations.push(EpdOperation {
+                opcode: opcode.to_string(),
+                operands,
+            }),
+        }
+    }
+
+    pub fn remove(&mut self, opcode: &str) {
+        self.operations
+            .retain(|operation| operation.opcode != opcode)
+    }
+
+    #[inline]
+    pub fn id(&self) -> Option<&str> {
+        self.get_operand("id")
+    }
+
+    //Comment operations c0 to c9
+    pub fn comment(&self, index: u8) -> Option<&str> {
+        self.get_operand(&format!("c{index}"))
+    }
+
+    //Centipawn evaluation from the side to move's point of view
+    pub fn centipawn_evaluation(&self) -> Option<i32> {
+        self.get_operand("ce")?.parse().ok()
+    }
+
+    //Best moves resolved against the position, operands that are not legal moves are skipped
+    pub fn best_moves(&self) -> Vec<Move> {
+        self.resolve_moves("bm")
+    }
+
+    pub fn avoid_moves(&self) -> Vec<Move> {
+        self.resolve_moves("am")
+    }
+
+    //Predicted variation, resolved move by move. Stops at the first illegal move.
+    pub fn predicted_variation(&self) -> Vec<Move> {
+        let mut board = self.board();
+        let mut result = Vec::new();
+        for notation in self
+            .get("pv")
+            .map_or(&[][..], |operation| &operation.operands)
+        {
+            let Some(mv) = parse_move(&board, notation) else {
+                break;
+            };
+            board.apply_move(mv);
+            result.push(mv);
+        }
+        result
+    }
+
+    //Perft depth operations D1..Dn as (depth, nodes) pairs
+    pub fn perft_depths(&self) -> Vec<(u8, u128)> {
+        self.operations
+            .iter()
+            .filter_map(|operation| {
+                let depth = operation.opcode.strip_prefix('D')?.parse().ok()?;
+                let nodes = operation.operands.first()?.parse().ok()?;
+                Some((depth, nodes))
+            })
+            .collect()
+    }
+
+    pub fn set_best_moves(&mut self, moves: &[Move]) {
+        let board = self.board();
+        let operands = moves.iter().map(|&mv| board.move_to_san(mv)).collect();
+        self.set("bm", operands)
+    }
+
+    pub fn set_predicted_variation(&mut self, moves: &[Move]) {
+        let mut board = self.board();
+        let mut operands = Vec::new();
+        for &mv in moves {
+            operands.push(board.move_to_san(mv));
+            board.apply_move(mv);
+        }
+        self.set("pv", operands)
+    }
+
+    fn resolve_moves(&self, opcode: &str) -> Vec<Move> {
+        let board = self.board();
+        self.get(opcode)
+            .map_or(&[][..], |operation| &operation.operands)
+            .iter()
+            .filter_map(|notation| parse_move(&board, notation))
+            .collect()
+    }
+}
+
+impl Display for Epd {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        write!(
+            formatter,
+            "{} {} {} {}",
+            self.fen.board.join("/"),
+            self.fen.side_to_move,
+            self.fen.castle_rights,
+            self.fen.en_passant_square
+        )?;
+
+        for operation in &self.operations {
+            write!(formatter, " {}", operation.opcode)?;
+            let string_operation = operation.opcode == "id"
+                || (operation.opcode.len() == 2
+                    && operation.opcode.starts_with('c')
+                    && operation.opcode.as_bytes()[1].is_ascii_digit());
+            for operand in &operation.operands {
+                if string_operation
+                    || operand.is_empty()
+                    || operand.contains(|character: char| {
+                        character.is_whitespace() || character == ';' || character == '"'
+                    })
+                {
+                    let escaped = operand.replace('\\', "\\\\").replace('"', "\\\"");
+                    write!(formatter, " \"{escaped}\"")?;
+                } else {
+                    write!(formatter, " {operand}")?;
+                }
+            }
+            write!(formatter, ";")?;
+        }
+
+        if self.move_counters {
+            if self.get("hmvc").is_none() {
+                write!(formatter, " hmvc {};", self.fen.half_move_counter)?;
+            }
+            if self.get("fmvn").is_none() {
+                write!(formatter, " fmvn {};", self.fen.full_move_counter)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_move(board: &ChessBoard, notation: &str) -> Option<Move> {
+    board
+        .parse_san_move(notation)
+        .or_else(|| board.parse_uci_move(notation))
+}
+
+fn parse_operations(text: &str) -> std::result::Result<Vec<EpdOperation>, String> {
+    let mut operations = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        //stray semicolons, as in "<fen> ;D1 20", separate nothing
+        while chars
+            .next_if(|character| character.is_whitespace() || *character == ';')
+            .is_some()
+        {}
+        if chars.peek().is_none() {
+            return Ok(operations);
+        }
+
+        let mut operation = EpdOperation::default();
+        while let Some(character) =
+            chars.next_if(|character| !character.is_whitespace() && *character != ';')
+        {
+            operation.opcode.push(character);
+        }
+
+        loop {
+            while chars
+                .next_if(|character| character.is_whitespace())
+                .is_some()
+            {}
+            match chars.next() {
+                Some(';') => break,
+                Some('"') => {
+                    let mut operand = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            //quotes and backslashes inside strings are escaped with a backslash
+                            Some('\\') => {
+                                let escaped = chars.next_if(|next| *next == '"' || *next == '\\');
+                                operand.push(escaped.unwrap_or('\\'));
+                            }
+                            Some(character) => operand.push(character),
+                            None => {
+                                return Err(format!(
+                                    "unterminated string in operation '{}'",
+                                    operation.opcode
+                                ))
+                            }
+                        }
+                    }
+                    operation.operands.push(operand);
+                }
+                Some(character) => {
+                    let mut operand = character.to_string();
+                    while let Some(character) =
+                        chars.next_if(|character| !character.is_whitespace() && *character != ';')
+                    {
+                        operand.push(character);
+                    }
+                    operation.operands.push(operand);
+                }
+                //a missing semicolon after the last operation is tolerated
+                None => {
+                    operations.push(operation);
+                    return Ok(operations);
+                }
+            }
+        }
+
+        operations.push(operation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Epd;
+    use crate::Move;
+
+    fn assert_stable(line: &str, expected: &str) {
+        let epd = Epd::from_str(line).unwrap();
+        assert_eq!(epd.to_string(), expected);
+
+        let reparsed = Epd::from_str(expected).unwrap();
+        assert_eq!(reparsed.to_string(), expected);
+        assert_eq!(reparsed.fen.to_string(), epd.fen.to_string());
+        assert_eq!(reparsed.id(), epd.id());
+    }
+
+    #[test]
+    fn trailing_move_counters_are_kept() {
+        assert_stable(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 3 42 ;D1 14 ;D2 191",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - D1 14; D2 191; hmvc 3; fmvn 42;",
+        );
+        assert_stable(
+            "4k3/8/8/8/8/8/8/4K3 b - - hmvc 7; fmvn 12;",
+            "4k3/8/8/8/8/8/8/4K3 b - - hmvc 7; fmvn 12;",
+        );
+        assert_stable(
+            "4k3/8/8/8/8/8/8/4K3 w - - bm Kd2;",
+            "4k3/8/8/8/8/8/8/4K3 w - - bm Kd2;",
+        );
+    }
+
+    #[test]
+    fn quotes_in_strings_are_escaped() {
+        let line = r#"4k3/8/8/8/8/8/8/4K3 w - - id "say \"hi\" \\o/"; c0 a"b;"#;
+        let epd = Epd::from_str(line).unwrap();
+        assert_eq!(epd.id(), Some(r#"say "hi" \o/"#));
+        assert_eq!(epd.comment(0), Some(r#"a"b"#));
+        assert_stable(
+            line,
+            r#"4k3/8/8/8/8/8/8/4K3 w - - id "say \"hi\" \\o/"; c0 "a\"b";"#,
+        );
+    }
+
+    //White has knights on b1 and f3, rooks on a1 and a5 and a pawn that can promote with check.
+    //Queenside castling is blocked by the knight.
+    const SAN_POSITION: &str = "r3k2r/1P6/8/R7/8/5N2/8/RN2K2R w KQkq -";
+
+    fn uci(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(Move::to_string).collect()
+    }
+
+    #[test]
+    fn san_operands_are_resolved() {
+        let epd = Epd::from_str(&format!(
+            "{SAN_POSITION} bm Nbd2 R1a3 O-O bxa8=Q+ b8N; am R5a3 Nfd2 O-O-O;"
+        ))
+        .unwrap();
+        assert_eq!(
+            uci(&epd.best_moves()),
+            ["b1d2", "a1a3", "e1g1", "b7a8q", "b7b8n"]
+        );
+        assert_eq!(uci(&epd.avoid_moves()), ["a5a3", "f3d2"]);
+
+        let mate = Epd::from_str("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#;").unwrap();
+        assert_eq!(uci(&mate.best_moves()), ["a1a8"]);
+    }
+
+    #[test]
+    fn predicted_variation_stops_at_illegal_moves() {
+        let epd = Epd::from_str(&format!("{SAN_POSITION} pv Nbd2 Ke7 O-O Kd6 bxa8=Q;")).unwrap();
+        assert_eq!(
+            uci(&epd.predicted_variation()),
+            ["b1d2", "e8e7", "e1g1", "e7d6", "b7a8q"]
+        );
+
+        let epd = Epd::from_str(&format!("{SAN_POSITION} pv Nbd2 Nbd2 O-O;")).unwrap();
+        assert_eq!(uci(&epd.predicted_variation()), ["b1d2"]);
+    }
+
+    #[test]
+    fn moves_are_written_in_san() {
+        let mut epd = Epd::from_str(SAN_POSITION).unwrap();
+        let board = epd.board();
+        let moves: Vec<Move> = ["b1d2", "a5a3", "e1g1", "b7a8q"]
+            .iter()
+            .map(|mv| board.parse_uci_move(mv).unwrap())
+            .collect();
+        epd.set_best_moves(&moves);
+        assert_eq!(
+            epd.to_string(),
+            format!("{SAN_POSITION} bm Nbd2 R5a3 O-O bxa8=Q+;")
+        );
+        assert!(Epd::from_str(&epd.to_string()).unwrap().best_moves() == moves);
+
+        let variation: Vec<Move> = {
+            let mut board = board;
+            ["f3d2", "e8d8", "b7b8q"]
+                .iter()
+                .map(|notation| {
+                    let mv = board.parse_uci_move(notation).unwrap();
+                    board.apply_move(mv);
+                    mv
+                })
+                .collect()
+        };
+        epd.set_predicted_variation(&variation);
+        assert_eq!(epd.get("pv").unwrap().operands, ["Nfd2", "Kd8", "b8=Q+"]);
+        assert!(
+            Epd::from_str(&epd.to_string())
+                .unwrap()
+                .predicted_variation()
+                == variation
+        );
+    }
+}
@@ -1,5 +1,6 @@
 mod bitboard;
 mod castle_rights;
+mod epd_struct;
 mod fen_struct;
 mod r#move;
 mod move_flags;
@@ -11,6 +12,7 @@ mod zobrist;
 
 pub use bitboard::Bitboard;
 pub use castle_rights::CastleRights;
+pub use epd_struct::{Epd, EpdOperation};
 pub use fen_struct::FEN;
 pub use move_flags::MoveFlag;
 pub use move_history::MoveHistory;
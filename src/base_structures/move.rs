@@ -55,6 +55,12 @@ impl Move {
         self.get_flag() == MoveFlag::EN_PASSANT
     }
 
+    #[inline]
+    pub fn is_castle(&self) -> bool {
+        self.get_flag() == MoveFlag::KING_SIDE_CASTLE
+            || self.get_flag() == MoveFlag::QUEEN_SIDE_CASTLE
+    }
+
     #[inline]
     pub fn is_promotion(&self) -> bool {
         self.get_flag() & MoveFlag::KNIGHT_PROMOTION > 0
@@ -0,0 +1,165 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+};
+
+use spear::{
+    dedup_packed_files, write_packed_file, ChessBoardPacked, DatasetStats, DedupOptions,
+    PackedReader, PositionFilter,
+};
+
+const USAGE: &str = "usage: dataset_tool stats [--threads N] <file>...
+       dataset_tool dedup --output <file> [--run-size N] [--temp-dir DIR] [--checksum] <file>...
+       dataset_tool filter --output <file> [--skip-check] [--skip-insufficient]
+                           [--skip-winning-capture CP] [--checksum] <file>...";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let mut files = Vec::new();
+    let mut output = None;
+    let mut threads = 1;
+    let mut options = DedupOptions::default();
+    let mut filters = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => threads = value,
+                _ => return usage_error(),
+            },
+            "--output" => match args.next() {
+                Some(value) => output = Some(PathBuf::from(value)),
+                None => return usage_error(),
+            },
+            "--run-size" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => options.run_size = value,
+                None => return usage_error(),
+            },
+            "--temp-dir" => match args.next() {
+                Some(value) => options.temp_dir = PathBuf::from(value),
+                None => return usage_error(),
+            },
+            "--checksum" => options.checksum = true,
+            "--skip-check" => filters.push(PositionFilter::InCheck),
+            "--skip-insufficient" => filters.push(PositionFilter::InsufficientMaterial),
+            "--skip-winning-capture" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => filters.push(PositionFilter::WinningCapture(value)),
+                None => return usage_error(),
+            },
+            _ if !arg.starts_with("--") => files.push(PathBuf::from(arg)),
+            _ => return usage_error(),
+        }
+    }
+
+    if files.is_empty() {
+        return usage_error();
+    }
+
+    match (command.as_deref(), output) {
+        (Some("stats"), None) => stats(&files, threads),
+        (Some("dedup"), Some(output)) => match dedup_packed_files(&files, &output, &options) {
+            Ok(summary) => {
+                let duplicates = summary.input_records - summary.unique_records;
+                println!(
+                    "{} records, {} unique, {} duplicates ({:.2}%), {} runs",
+                    summary.input_records,
+                    summary.unique_records,
+                    duplicates,
+                    duplicates as f64 * 100.0 / summary.input_records.max(1) as f64,
+                    summary.runs
+                );
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                eprintln!("dedup failed: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        (Some("filter"), Some(output)) => {
+            let filter = PositionFilter::Any(filters);
+            match filter_files(&files, &output, &filter, options.checksum) {
+                Ok((read, kept)) => {
+                    println!("{read} records, {kept} kept, {} rejected", read - kept);
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("filter failed: {error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        _ => usage_error(),
+    }
+}
+
+//Returns the number of records read and written
+fn filter_files(
+    files: &[PathBuf],
+    output: &Path,
+    filter: &PositionFilter,
+    checksum: bool,
+) -> io::Result<(u64, u64)> {
+    let mut read = 0;
+    let kept = write_packed_file(output, checksum, |writer| {
+        for path in files {
+            let reader: PackedReader<_, ChessBoardPacked> =
+                PackedReader::new(BufReader::new(File::open(path)?))?;
+            for pack in reader {
+                let pack = pack?;
+                read += 1;
+                if filter.accepts_record(&pack) {
+                    writer.write(&pack)?;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok((read, kept))
+}
+
+//Every thread reads every n-th file, the results are merged at the end
+fn stats(files: &[PathBuf], threads: usize) -> ExitCode {
+    let results = thread::scope(|scope| {
+        let handles = (0..threads.min(files.len()))
+            .map(|thread_index| {
+                scope.spawn(move || {
+                    let mut stats = DatasetStats::default();
+                    for path in files.iter().skip(thread_index).step_by(threads) {
+                        match DatasetStats::from_file(path) {
+                            Ok(file_stats) => stats.merge(&file_stats),
+                            Err(error) => return Err(format!("{}: {error}", path.display())),
+                        }
+                    }
+                    Ok(stats)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut stats = DatasetStats::default();
+    for result in results {
+        match result {
+            Ok(thread_stats) => stats.merge(&thread_stats),
+            Err(error) => {
+                eprintln!("failed to read {error}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    print!("{stats}");
+    ExitCode::SUCCESS
+}
+
+fn usage_error() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
@@ -0,0 +1,103 @@
+use std::process::ExitCode;
+
+use spear::{random_position, PerftSuite, ReferenceMoveGen, FEN};
+
+const USAGE: &str =
+    "usage: movegen_fuzz [--games N] [--plies N] [--seed N] [--material KRPvKR]... [suite.epd]";
+
+//Random positions played from for every --material signature
+const MATERIAL_POSITIONS: u64 = 32;
+
+//Positions with pins, en passant discovered checks, castling through attacks and rank-pinned
+//promotion pushes, played from in addition to the perft suite positions
+const EXTRA_FENS: [&str; 9] = [
+    "8/8/8/KPp4r/8/8/8/6k1 w - c6 0 1",
+    "8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1",
+    "8/8/3k4/8/2pP4/8/8/B3K3 b - d3 0 1",
+    "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+    "r3k2r/8/8/8/3q4/8/8/R3K2R w KQkq - 0 1",
+    "4k3/8/8/2b5/8/8/8/R3K2R w KQ - 0 1",
+    "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1",
+    "8/K1P4r/8/8/8/8/8/4k3 w - - 0 1",
+];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut games = 100;
+    let mut plies = 200;
+    let mut seed = 0x5eed;
+    let mut fens: Vec<FEN> = EXTRA_FENS.iter().map(|fen| FEN::from_str(fen)).collect();
+    let mut materials = Vec::new();
+
+    let mut index = 0;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        index += 1;
+        let value = args.get(index).and_then(|value| value.parse::<u64>().ok());
+        match (arg, value) {
+            ("--games", Some(value)) => games = value as usize,
+            ("--plies", Some(value)) => plies = value as usize,
+            ("--seed", Some(value)) => seed = value,
+            ("--material", _) if index < args.len() => materials.push(args[index].clone()),
+            (path, _) if !path.starts_with("--") => {
+                match PerftSuite::load(path) {
+                    Ok(suite) => fens.extend(suite.entries().iter().map(|entry| entry.fen.clone())),
+                    Err(error) => {
+                        eprintln!("failed to load {path}: {error}");
+                        return ExitCode::from(2);
+                    }
+                }
+                continue;
+            }
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+        index += 1;
+    }
+
+    for material in &materials {
+        for position_index in 0..MATERIAL_POSITIONS {
+            match random_position(material, seed.wrapping_add(position_index)) {
+                Ok(board) => fens.push(board.get_fen()),
+                Err(error) => {
+                    eprintln!("{error}");
+                    return ExitCode::from(2);
+                }
+            }
+        }
+    }
+
+    if fens.len() == EXTRA_FENS.len() {
+        fens.push(FEN::start_position());
+        fens.push(FEN::kiwipete_position());
+    }
+
+    match ReferenceMoveGen::fuzz(&fens, games, plies, seed) {
+        Ok(positions) => {
+            println!(
+                "{positions} positions from {} fens matched the reference generator",
+                fens.len()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(mismatch) => {
+            println!("Mismatch in {}", mismatch.fen);
+            let format = |moves: &[spear::Move]| {
+                moves
+                    .iter()
+                    .map(|mv| mv.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            println!("Missing in map_moves: {}", format(&mismatch.missing));
+            println!("Extra in map_moves: {}", format(&mismatch.extra));
+            if mismatch.capture_mismatch {
+                println!("map_captures is not the capture subset of the legal moves");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
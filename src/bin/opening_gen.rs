@@ -0,0 +1,64 @@
+use std::process::ExitCode;
+
+use spear::{Epd, MoveSelection, OpeningGenerator, OpeningGeneratorOptions, FEN};
+
+const USAGE: &str = "usage: opening_gen [--count N] [--plies N] [--seed N] [--fen FEN] [--min-moves N] [--avoid-captures] [--epd]";
+
+fn main() -> ExitCode {
+    let mut count = 100;
+    let mut fen = FEN::start_position();
+    let mut epd = false;
+    let mut options = OpeningGeneratorOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--count" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => count = value,
+                None => return usage_error(),
+            },
+            "--plies" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => options.plies = value,
+                None => return usage_error(),
+            },
+            "--seed" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => options.seed = value,
+                None => return usage_error(),
+            },
+            "--min-moves" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => options.min_legal_moves = value,
+                None => return usage_error(),
+            },
+            "--fen" => match args.next() {
+                Some(value) if FEN::validate_fen(&value) => fen = FEN::from_str(&value),
+                _ => return usage_error(),
+            },
+            "--avoid-captures" => options.selection = MoveSelection::AvoidCaptures,
+            "--epd" => epd = true,
+            _ => return usage_error(),
+        }
+    }
+
+    let mut generator = OpeningGenerator::new(&fen, options);
+    for board in generator.by_ref().take(count) {
+        if epd {
+            println!("{}", Epd::from_fen(board.get_fen()));
+        } else {
+            println!("{}", board.get_fen());
+        }
+    }
+
+    if generator.generated_count() < count {
+        eprintln!(
+            "only {} unique positions found, the walk is too short or too restricted",
+            generator.generated_count()
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage_error() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
@@ -0,0 +1,91 @@
+use std::process::ExitCode;
+
+use spear::{ChessBoard, Perft, UciEngine, FEN};
+
+const USAGE: &str =
+    "usage: perft_diff --engine <command> [--engine-arg ARG]... [--fen FEN] --depth N";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut engine_command = None;
+    let mut engine_args = Vec::new();
+    let mut fen = FEN::start_position();
+    let mut depth = None;
+
+    let mut index = 0;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        index += 1;
+        match arg {
+            "--engine" if index < args.len() => {
+                engine_command = Some(args[index].clone());
+                index += 1;
+            }
+            "--engine-arg" if index < args.len() => {
+                engine_args.push(args[index].clone());
+                index += 1;
+            }
+            "--depth" if index < args.len() => {
+                depth = args[index].parse::<u8>().ok();
+                index += 1;
+            }
+            "--fen" => {
+                let mut parts = Vec::new();
+                while index < args.len() && !args[index].starts_with("--") {
+                    parts.push(args[index].as_str());
+                    index += 1;
+                }
+                let fen_string = parts.join(" ");
+                fen = match fen_string.as_str() {
+                    "startpos" => FEN::start_position(),
+                    "kiwipete" => FEN::kiwipete_position(),
+                    _ if FEN::validate_fen(&fen_string) => FEN::from_string(fen_string),
+                    _ => {
+                        eprintln!("invalid fen '{fen_string}'");
+                        return ExitCode::from(2);
+                    }
+                };
+            }
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let (Some(engine_command), Some(depth)) = (engine_command, depth) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let mut engine = match UciEngine::launch(&engine_command, &engine_args) {
+        Ok(engine) => engine,
+        Err(error) => {
+            eprintln!("failed to launch {engine_command}: {error}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let board = ChessBoard::from_fen(&fen);
+    match Perft::find_divide_mismatch(&mut engine, &board, depth) {
+        Ok(None) => {
+            println!("No mismatch found at depth {depth}");
+            ExitCode::SUCCESS
+        }
+        Ok(Some(diff)) => {
+            if diff.moves.is_empty() {
+                println!("Mismatch in the root position");
+            } else {
+                println!("Mismatch after moves: {}", diff.moves.join(" "));
+            }
+            println!("FEN: {}", diff.fen);
+            println!("Missing in Spear: {}", diff.missing.join(" "));
+            println!("Extra in Spear: {}", diff.extra.join(" "));
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("engine communication failed: {error}");
+            ExitCode::from(2)
+        }
+    }
+}
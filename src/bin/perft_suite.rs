@@ -0,0 +1,53 @@
+use std::process::ExitCode;
+
+use spear::PerftSuite;
+
+const USAGE: &str = "usage: perft_suite <suite.epd> [--max-depth N] [--threads N] [--json]";
+
+fn main() -> ExitCode {
+    let mut path = None;
+    let mut max_depth = u8::MAX;
+    let mut threads = 1;
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-depth" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => max_depth = value,
+                None => return usage_error(),
+            },
+            "--threads" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => threads = value,
+                None => return usage_error(),
+            },
+            "--json" => json = true,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return usage_error(),
+        }
+    }
+
+    let Some(path) = path else {
+        return usage_error();
+    };
+
+    let suite = match PerftSuite::load(&path) {
+        Ok(suite) => suite,
+        Err(error) => {
+            eprintln!("failed to load {path}: {error}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let summary = suite.run_and_print(max_depth, threads, json);
+    if summary.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn usage_error() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
@@ -0,0 +1,161 @@
+use std::{
+    io::{self, BufRead, Write},
+    time::Instant,
+};
+
+use spear::{Bitboard, ChessBoard, Perft, StringUtils, FEN};
+
+const HELP: &str = "commands:
+  position startpos|kiwipete|fen <fen> [moves <move>...]
+  d                 draw the board
+  moves             list legal moves in UCI and SAN
+  move <move>...    make moves given in UCI or SAN
+  undo              take back the last move
+  perft <depth>     count leaf nodes
+  divide <depth>    count leaf nodes per root move
+  key               print the zobrist key
+  fen               print the fen
+  checkers          print pieces giving check
+  pins              print pinned pieces of the side to move
+  quit";
+
+struct Repl {
+    board: ChessBoard,
+    history: Vec<ChessBoard>,
+}
+
+fn main() {
+    let mut repl = Repl {
+        board: ChessBoard::from_fen(&FEN::start_position()),
+        history: Vec::new(),
+    };
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        match command {
+            "quit" | "exit" => break,
+            "help" => println!("{HELP}"),
+            "position" => repl.position(args),
+            "d" => repl.board.draw_board(),
+            "moves" => repl.moves(),
+            "move" => repl.make_moves(args),
+            "undo" => repl.undo(),
+            "perft" => repl.perft(args, false),
+            "divide" => repl.perft(args, true),
+            "go" if args.first() == Some(&"perft") => repl.perft(&args[1..], true),
+            "key" => println!("{}", repl.board.get_key()),
+            "fen" => println!("{}", repl.board.get_fen()),
+            "checkers" => print_squares("checkers", repl.board.get_checkers()),
+            "pins" => print_squares("pins", repl.board.get_pinned_pieces()),
+            _ => println!("unknown command '{command}', type 'help' for the list of commands"),
+        }
+
+        io::stdout().flush().ok();
+    }
+}
+
+impl Repl {
+    fn position(&mut self, args: &[&str]) {
+        let moves_index = args
+            .iter()
+            .position(|&arg| arg == "moves")
+            .unwrap_or(args.len());
+
+        let fen = match args.first() {
+            Some(&"startpos") => FEN::start_position(),
+            Some(&"kiwipete") => FEN::kiwipete_position(),
+            Some(&"fen") => {
+                let fen = args[1..moves_index].join(" ");
+                if !FEN::validate_fen(&fen) {
+                    println!("invalid fen '{fen}'");
+                    return;
+                }
+                FEN::from_string(fen)
+            }
+            _ => {
+                println!("usage: position startpos|kiwipete|fen <fen> [moves <move>...]");
+                return;
+            }
+        };
+
+        self.board = ChessBoard::from_fen(&fen);
+        self.history.clear();
+        if moves_index < args.len() {
+            self.make_moves(&args[moves_index + 1..]);
+        }
+    }
+
+    fn moves(&self) {
+        let moves = self.board.get_legal_moves();
+        for mv in &moves {
+            println!("{mv} {}", self.board.move_to_san(*mv));
+        }
+        println!("{} legal moves", moves.len());
+    }
+
+    fn make_moves(&mut self, moves: &[&str]) {
+        for &notation in moves {
+            let Some(mv) = self
+                .board
+                .parse_uci_move(notation)
+                .or_else(|| self.board.parse_san_move(notation))
+            else {
+                println!("illegal move '{notation}'");
+                return;
+            };
+
+            self.history.push(self.board);
+            self.board.apply_move(mv);
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.history.pop() {
+            Some(board) => self.board = board,
+            None => println!("nothing to undo"),
+        }
+    }
+
+    fn perft(&self, args: &[&str], divide: bool) {
+        let Some(depth) = args.first().and_then(|depth| depth.parse::<u8>().ok()) else {
+            println!("usage: perft|divide <depth>");
+            return;
+        };
+
+        let timer = Instant::now();
+        let results = Perft::perft_parallel_divide::<true>(&self.board, depth, 1);
+        let duration = timer.elapsed().as_millis();
+        let nodes: u128 = if depth == 0 {
+            1
+        } else {
+            results.iter().map(|(_, nodes)| nodes).sum()
+        };
+
+        if divide {
+            for (mv, nodes) in &results {
+                println!("{mv} - {nodes}");
+            }
+        }
+
+        println!(
+            "{nodes} nodes, {}, {}n/s",
+            StringUtils::time_to_string(duration),
+            StringUtils::large_number_to_string(StringUtils::nodes_per_second(nodes, duration))
+        );
+    }
+}
+
+fn print_squares(name: &str, squares: Bitboard) {
+    let mut names = Vec::new();
+    squares.map(|square| names.push(square.to_string()));
+    println!("{name}: {}", names.join(" "));
+}
@@ -1,5 +1,9 @@
+use std::io;
+
 use crate::{base_structures::Side, Bitboard, ChessBoard};
 
+use super::packed_io::{invalid_data, PackedRecord};
+
 pub struct ChessBoardPacked {
     board: [Bitboard; 4],
     side_to_move: Side,
@@ -8,6 +12,9 @@ pub struct ChessBoardPacked {
 }
 
 impl ChessBoardPacked {
+    //4 compressed bitboards (u64) | side to move u8 | score u16 | result i8, little endian
+    pub const SIZE: usize = 36;
+
     pub fn from_board(board: &ChessBoard, score: f32) -> Self {
         Self {
             board: board_to_compressed(board),
@@ -32,6 +39,12 @@ impl ChessBoardPacked {
         self.result
     }
 
+    //Score of the side to move, 0.0 to 1.0
+    #[inline]
+    pub fn get_score(&self) -> f32 {
+        self.score as f32 / u16::MAX as f32
+    }
+
     #[inline]
     pub fn get_white_perspective_score(&self) -> f32 {
         let stm_score = self.score as f32 / u16::MAX as f32;
@@ -46,9 +59,77 @@ impl ChessBoardPacked {
     pub fn apply_result(&mut self, winner: Side) {
         self.result = if winner == Side::WHITE { 1 } else { -1 }
     }
+
+    pub fn to_bytes(&self) -> [u8; ChessBoardPacked::SIZE] {
+        let mut bytes = [0; ChessBoardPacked::SIZE];
+        write_compressed_board(&self.board, &mut bytes[0..32]);
+        bytes[32] = self.side_to_move.get_raw();
+        bytes[33..35].copy_from_slice(&self.score.to_le_bytes());
+        bytes[35] = self.result as u8;
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(invalid_data("board record is too short"));
+        }
+
+        Ok(Self {
+            board: read_compressed_board(&bytes[0..32])?,
+            side_to_move: read_side(bytes[32])?,
+            score: u16::from_le_bytes([bytes[33], bytes[34]]),
+            result: bytes[35] as i8,
+        })
+    }
+}
+
+impl PackedRecord for ChessBoardPacked {
+    const SIZE: usize = ChessBoardPacked::SIZE;
+    const KIND: u8 = 0;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_bytes())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes(bytes)
+    }
 }
 
-fn board_to_compressed(board: &ChessBoard) -> [Bitboard; 4] {
+pub(super) fn write_compressed_board(board: &[Bitboard; 4], bytes: &mut [u8]) {
+    for (chunk, bitboard) in bytes.chunks_exact_mut(8).zip(board) {
+        chunk.copy_from_slice(&bitboard.get_raw().to_le_bytes());
+    }
+}
+
+//Rejects squares with the piece code 0b110, the only 3 bit value that is neither a piece
+//nor the 0b111 empty marker
+pub(super) fn read_compressed_board(bytes: &[u8]) -> io::Result<[Bitboard; 4]> {
+    let mut board = [Bitboard::EMPTY; 4];
+    for (bitboard, chunk) in board.iter_mut().zip(bytes.chunks_exact(8)) {
+        *bitboard = Bitboard::from_raw(u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+
+    let invalid = board[1] & board[2] & board[0].inverse();
+    if invalid.is_not_empty() {
+        return Err(invalid_data(format!(
+            "invalid piece code on square {}",
+            invalid.ls1b_square()
+        )));
+    }
+
+    Ok(board)
+}
+
+pub(super) fn read_side(byte: u8) -> io::Result<Side> {
+    match byte {
+        0 => Ok(Side::WHITE),
+        1 => Ok(Side::BLACK),
+        _ => Err(invalid_data(format!("invalid side to move {byte}"))),
+    }
+}
+
+pub(super) fn board_to_compressed(board: &ChessBoard) -> [Bitboard; 4] {
     let mut result = [Bitboard::FULL; 4];
 
     board.get_occupancy().map(|square| {
@@ -70,3 +151,36 @@ fn board_to_compressed(board: &ChessBoard) -> [Bitboard; 4] {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ChessBoardPacked;
+    use crate::{ChessBoard, FEN};
+
+    #[test]
+    fn round_trip_keeps_fen() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b - - 0 1";
+        let board = ChessBoard::from_fen(&FEN::from_str(fen));
+        let pack = ChessBoardPacked::from_board(&board, 0.25);
+        let pack = ChessBoardPacked::from_bytes(&pack.to_bytes()).unwrap();
+
+        assert_eq!(
+            ChessBoard::from_board_pack(&pack).get_fen().to_string(),
+            fen
+        );
+        assert!((pack.get_score() - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rejects_invalid_piece_code() {
+        let board = ChessBoard::from_fen(&FEN::start_position());
+        let mut bytes = ChessBoardPacked::from_board(&board, 0.5).to_bytes();
+
+        //Piece code 0b110 on the first square
+        bytes[0] &= !1;
+        bytes[8] |= 1;
+        bytes[16] |= 1;
+
+        assert!(ChessBoardPacked::from_bytes(&bytes).is_err());
+    }
+}
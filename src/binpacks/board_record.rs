@@ -0,0 +1,238 @@
+use std::io;
+
+use crate::{Bitboard, CastleRights, ChessBoard, Move, Side, Square};
+
+use super::{
+    board_pack::{board_to_compressed, read_compressed_board, read_side, write_compressed_board},
+    packed_io::{invalid_data, PackedRecord},
+};
+
+//Training record keeping the whole position state, so that `to_board` restores the fen
+//and zobrist key of the original board exactly
+#[derive(Clone, Copy, PartialEq)]
+pub struct ChessBoardRecord {
+    board: [Bitboard; 4],
+    side_to_move: Side,
+    castle_rights: CastleRights,
+    en_passant: Square,
+    half_moves: u8,
+    full_moves: u16,
+    //centipawns from the side to move's point of view
+    pub eval: i16,
+    pub result: GameResult,
+    pub best_move: Move,
+    pub ply: u16,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum GameResult {
+    #[default]
+    Unknown,
+    WhiteWin,
+    Draw,
+    BlackWin,
+}
+
+impl GameResult {
+    #[inline]
+    pub fn to_raw(self) -> u8 {
+        self as u8
+    }
+
+    #[inline]
+    pub fn from_raw(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Unknown),
+            1 => Some(Self::WhiteWin),
+            2 => Some(Self::Draw),
+            3 => Some(Self::BlackWin),
+            _ => None,
+        }
+    }
+
+    //1.0 for a white win, 0.5 for a draw, None when unknown
+    pub fn white_score(self) -> Option<f32> {
+        match self {
+            Self::Unknown => None,
+            Self::WhiteWin => Some(1.0),
+            Self::Draw => Some(0.5),
+            Self::BlackWin => Some(0.0),
+        }
+    }
+}
+
+impl ChessBoardRecord {
+    //4 compressed bitboards (u64) | side to move u8 | castle rights u8 | en passant u8 |
+    //half moves u8 | full moves u16 | eval i16 | result u8 | best move u16 | ply u16 |
+    //3 reserved bytes, little endian. En passant is 64 when there is no en passant square.
+    pub const SIZE: usize = 48;
+
+    pub fn from_board(board: &ChessBoard, eval: i16, result: GameResult) -> Self {
+        Self {
+            board: board_to_compressed(board),
+            side_to_move: board.side_to_move(),
+            castle_rights: board.castle_rights(),
+            en_passant: board.en_passant_square(),
+            half_moves: board.half_move_counter(),
+            full_moves: board.full_move_counter(),
+            eval,
+            result,
+            best_move: Move::NULL,
+            ply: 0,
+        }
+    }
+
+    #[inline]
+    pub fn to_board(&self) -> ChessBoard {
+        ChessBoard::from_board_record(self)
+    }
+
+    #[inline]
+    pub fn get_board(&self) -> &[Bitboard; 4] {
+        &self.board
+    }
+
+    #[inline]
+    pub fn get_side_to_move(&self) -> Side {
+        self.side_to_move
+    }
+
+    #[inline]
+    pub fn get_castle_rights(&self) -> CastleRights {
+        self.castle_rights
+    }
+
+    #[inline]
+    pub fn get_en_passant_square(&self) -> Square {
+        self.en_passant
+    }
+
+    #[inline]
+    pub fn get_half_move_counter(&self) -> u8 {
+        self.half_moves
+    }
+
+    #[inline]
+    pub fn get_full_move_counter(&self) -> u16 {
+        self.full_moves
+    }
+
+    #[inline]
+    pub fn get_white_perspective_eval(&self) -> i16 {
+        if self.side_to_move == Side::WHITE {
+            self.eval
+        } else {
+            self.eval.saturating_neg()
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ChessBoardRecord::SIZE] {
+        let mut bytes = [0; ChessBoardRecord::SIZE];
+        write_compressed_board(&self.board, &mut bytes[0..32]);
+        bytes[32] = self.side_to_move.get_raw();
+        bytes[33] = self.castle_rights.get_raw();
+        bytes[34] = self.en_passant.get_raw();
+        bytes[35] = self.half_moves;
+        bytes[36..38].copy_from_slice(&self.full_moves.to_le_bytes());
+        bytes[38..40].copy_from_slice(&self.eval.to_le_bytes());
+        bytes[40] = self.result.to_raw();
+        bytes[41..43].copy_from_slice(&self.best_move.get_raw().to_le_bytes());
+        bytes[43..45].copy_from_slice(&self.ply.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(invalid_data("board record is too short"));
+        }
+
+        if bytes[33] > 0b1111 {
+            return Err(invalid_data(format!("invalid castle rights {}", bytes[33])));
+        }
+        if bytes[34] > Square::NULL.get_raw() {
+            return Err(invalid_data(format!(
+                "invalid en passant square {}",
+                bytes[34]
+            )));
+        }
+        let Some(result) = GameResult::from_raw(bytes[40]) else {
+            return Err(invalid_data(format!("invalid game result {}", bytes[40])));
+        };
+
+        Ok(Self {
+            board: read_compressed_board(&bytes[0..32])?,
+            side_to_move: read_side(bytes[32])?,
+            castle_rights: CastleRights::from_raw(bytes[33]),
+            en_passant: Square::from_raw(bytes[34]),
+            half_moves: bytes[35],
+            full_moves: u16::from_le_bytes([bytes[36], bytes[37]]),
+            eval: i16::from_le_bytes([bytes[38], bytes[39]]),
+            result,
+            best_move: Move::from_raw(u16::from_le_bytes([bytes[41], bytes[42]])),
+            ply: u16::from_le_bytes([bytes[43], bytes[44]]),
+        })
+    }
+}
+
+impl PackedRecord for ChessBoardRecord {
+    const SIZE: usize = ChessBoardRecord::SIZE;
+    const KIND: u8 = 2;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_bytes())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChessBoardRecord, GameResult};
+    use crate::{ChessBoard, FEN};
+
+    const FENS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+        "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 99 300",
+        "8/8/8/8/8/8/6k1/4K2R w K - 255 65535",
+    ];
+
+    #[test]
+    fn round_trip_keeps_fen_and_key() {
+        for fen in FENS {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            let record = ChessBoardRecord::from_board(&board, -120, GameResult::BlackWin);
+            let record = ChessBoardRecord::from_bytes(&record.to_bytes()).unwrap();
+            let result = record.to_board();
+
+            assert_eq!(result.get_fen().to_string(), fen);
+            assert_eq!(result.get_key().get_raw(), board.get_key().get_raw());
+            assert_eq!(record.get_full_move_counter(), board.full_move_counter());
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_records() {
+        let board = ChessBoard::from_fen(&FEN::start_position());
+        let bytes = ChessBoardRecord::from_board(&board, 0, GameResult::Draw).to_bytes();
+
+        //Piece code 0b110 on the first square
+        let mut corrupt = bytes;
+        corrupt[0] &= !1;
+        corrupt[8] |= 1;
+        corrupt[16] |= 1;
+        assert!(ChessBoardRecord::from_bytes(&corrupt).is_err());
+
+        let mut corrupt = bytes;
+        corrupt[33] = 0b10000;
+        assert!(ChessBoardRecord::from_bytes(&corrupt).is_err());
+
+        let mut corrupt = bytes;
+        corrupt[40] = 4;
+        assert!(ChessBoardRecord::from_bytes(&corrupt).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use std::io;
+
+use crate::{Bitboard, ChessBoard, Piece, Side, Square};
+
+use super::{
+    packed_io::{invalid_data, invalid_input, PackedRecord},
+    GameResult,
+};
+
+//32 byte bulletformat record, all values little endian. The position is stored from the side
+//to move's point of view: for black to move the board is flipped vertically and the colors
+//are swapped, so the side to move always plays "white".
+//occupancy u64 | 32 piece nibbles in occupancy order | score i16 | result u8 |
+//king square u8 | flipped opponent king square u8 | 3 extra bytes
+//A piece nibble holds the piece type and bit 3 for the opponent's pieces.
+//Score and result are relative to the side to move, result is 0 loss, 1 draw, 2 win.
+#[derive(Clone, Copy, PartialEq)]
+pub struct BulletFormat {
+    occupancy: Bitboard,
+    pieces: [u8; 16],
+    score: i16,
+    result: u8,
+    king_square: u8,
+    opponent_king_square: u8,
+    extra: [u8; 3],
+}
+
+const OPPONENT_FLAG: u8 = 8;
+
+impl BulletFormat {
+    pub const SIZE: usize = 32;
+
+    //Castle rights, en passant and move counters are not part of the format.
+    //Unknown results are stored as draws. Fails for boards with more than 32 pieces.
+    pub fn from_board(board: &ChessBoard, stm_score: i16, result: GameResult) -> io::Result<Self> {
+        if board.get_occupancy().pop_count() > 32 {
+            return Err(invalid_input("more than 32 pieces"));
+        }
+
+        let flip = board.side_to_move() == Side::BLACK;
+        let relative_square = |square: Square| if flip { square.flip() } else { square };
+
+        let mut occupancy = Bitboard::EMPTY;
+        board
+            .get_occupancy()
+            .map(|square| occupancy.set_bit(relative_square(square)));
+
+        let mut pieces = [0u8; 16];
+        let mut king_square = 0;
+        let mut opponent_king_square = 0;
+        let mut index = 0;
+        occupancy.map(|square| {
+            let board_square = relative_square(square);
+            let piece = board.get_piece_on_square(board_square);
+            let opponent = board.get_piece_color_on_square(board_square) != board.side_to_move();
+            if piece == Piece::KING {
+                if opponent {
+                    opponent_king_square = square.flip().get_raw();
+                } else {
+                    king_square = square.get_raw();
+                }
+            }
+
+            let nibble = piece.get_raw() | if opponent { OPPONENT_FLAG } else { 0 };
+            pieces[index / 2] |= nibble << (4 * (index % 2));
+            index += 1;
+        });
+
+        let result = match (result, flip) {
+            (GameResult::WhiteWin, false) | (GameResult::BlackWin, true) => 2,
+            (GameResult::WhiteWin, true) | (GameResult::BlackWin, false) => 0,
+            (GameResult::Draw | GameResult::Unknown, _) => 1,
+        };
+
+        Ok(Self {
+            occupancy,
+            pieces,
+            score: stm_score,
+            result,
+            king_square,
+            opponent_king_square,
+            extra: [0; 3],
+        })
+    }
+
+    //Rebuilds the board for the given side to move, which the format does not store
+    pub fn to_board(&self, side_to_move: Side) -> ChessBoard {
+        let flip = side_to_move == Side::BLACK;
+        let mut board = ChessBoard::default();
+        let mut index = 0;
+        self.occupancy.map(|square| {
+            let nibble = (self.pieces[index / 2] >> (4 * (index % 2))) & 0xF;
+            index += 1;
+
+            let piece = Piece::from_raw(nibble & 7);
+            let opponent = nibble & OPPONENT_FLAG > 0;
+            let (board_square, white) = if flip {
+                (square.flip(), opponent)
+            } else {
+                (square, !opponent)
+            };
+            if white {
+                board.set_piece_on_square::<true>(board_square, piece);
+            } else {
+                board.set_piece_on_square::<false>(board_square, piece);
+            }
+        });
+
+        board.set_side_to_move(side_to_move);
+        board.set_move_counters(0, 1);
+        board.refresh_phase();
+        board
+    }
+
+    #[inline]
+    pub fn get_score(&self) -> i16 {
+        self.score
+    }
+
+    //0 loss, 1 draw, 2 win for the side to move
+    #[inline]
+    pub fn get_result(&self) -> u8 {
+        self.result
+    }
+
+    #[inline]
+    pub fn get_king_square(&self) -> Square {
+        Square::from_raw(self.king_square)
+    }
+
+    //Opponent king square seen from the opponent's side of the board
+    #[inline]
+    pub fn get_opponent_king_square(&self) -> Square {
+        Square::from_raw(self.opponent_king_square)
+    }
+
+    pub fn to_bytes(&self) -> [u8; BulletFormat::SIZE] {
+        let mut bytes = [0; BulletFormat::SIZE];
+        bytes[0..8].copy_from_slice(&self.occupancy.get_raw().to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces);
+        bytes[24..26].copy_from_slice(&self.score.to_le_bytes());
+        bytes[26] = self.result;
+        bytes[27] = self.king_square;
+        bytes[28] = self.opponent_king_square;
+        bytes[29..32].copy_from_slice(&self.extra);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(invalid_data("bulletformat record is too short"));
+        }
+
+        let result = Self {
+            occupancy: Bitboard::from_raw(u64::from_le_bytes(bytes[0..8].try_into().unwrap())),
+            pieces: bytes[8..24].try_into().unwrap(),
+            score: i16::from_le_bytes([bytes[24], bytes[25]]),
+            result: bytes[26],
+            king_square: bytes[27],
+            opponent_king_square: bytes[28],
+            extra: bytes[29..32].try_into().unwrap(),
+        };
+
+        let piece_count = result.occupancy.pop_count() as usize;
+        if piece_count > 32 {
+            return Err(invalid_data("more than 32 pieces"));
+        }
+        if result.result > 2 {
+            return Err(invalid_data(format!("invalid result {}", result.result)));
+        }
+        let invalid_piece = (0..piece_count).any(|index| {
+            (result.pieces[index / 2] >> (4 * (index % 2))) & 7 > Piece::KING.get_raw()
+        });
+        if invalid_piece {
+            return Err(invalid_data("invalid piece nibble"));
+        }
+
+        Ok(result)
+    }
+}
+
+impl PackedRecord for BulletFormat {
+    const SIZE: usize = BulletFormat::SIZE;
+    const KIND: u8 = 4;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_bytes())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BulletFormat;
+    use crate::{ChessBoard, GameResult, Side, Square, FEN};
+
+    const FENS: [&str; 4] = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+        "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 37 112",
+    ];
+
+    //The format drops castle rights, en passant and move counters
+    fn placement_and_side(board: &ChessBoard) -> String {
+        let fen = board.get_fen().to_string();
+        let mut parts = fen.split(' ');
+        format!("{} {}", parts.next().unwrap(), parts.next().unwrap())
+    }
+
+    #[test]
+    fn round_trip_keeps_pieces_on_castle_and_en_passant_positions() {
+        for fen in FENS {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            let record = BulletFormat::from_board(&board, 120, GameResult::BlackWin).unwrap();
+            let record = BulletFormat::from_bytes(&record.to_bytes()).unwrap();
+            let result = record.to_board(board.side_to_move());
+
+            assert_eq!(placement_and_side(&result), placement_and_side(&board));
+            assert_eq!(result.castle_rights().get_raw(), 0);
+            assert!(result.en_passant_square() == Square::NULL);
+            assert_eq!(record.get_score(), 120);
+
+            let expected_result = if board.side_to_move() == Side::BLACK {
+                2
+            } else {
+                0
+            };
+            assert_eq!(record.get_result(), expected_result);
+        }
+    }
+
+    #[test]
+    fn rejects_more_than_32_pieces() {
+        let board = ChessBoard::from_fen(&FEN::from_str(
+            "rnbqkbnr/pppppppp/8/8/8/P7/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ));
+        assert!(BulletFormat::from_board(&board, 0, GameResult::Draw).is_err());
+    }
+}
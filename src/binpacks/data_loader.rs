@@ -0,0 +1,407 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::PathBuf,
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+use crate::Random;
+
+use super::{
+    packed_io::{invalid_input, PackedReader, PackedRecord},
+    FilterableRecord, PositionFilter,
+};
+
+#[derive(Clone)]
+pub struct DataLoaderOptions {
+    pub batch_size: usize,
+    //Records held in the shuffle reservoirs, split evenly between the threads
+    pub shuffle_buffer: usize,
+    //Files each thread keeps open and draws records from at random
+    pub interleaved_files: usize,
+    pub seed: u64,
+    //Passes over the files, 0 loops forever
+    pub epochs: usize,
+    //Chance of dropping a record before it reaches the reservoir
+    pub skip_probability: f64,
+    pub threads: usize,
+    //Batches each thread prepares ahead of the consumer
+    pub prefetch_batches: usize,
+}
+
+//Streams batches of records from packed files on background threads.
+//Each thread owns every n-th file and its own seeded generator, and batches are taken from
+//the threads in turn, so the same files, options and seed always produce the same batches.
+pub struct DataLoader<R: PackedRecord + Send + 'static> {
+    receivers: Vec<Receiver<io::Result<Vec<R>>>>,
+    next_receiver: usize,
+    workers: Vec<JoinHandle<()>>,
+}
+
+struct LoaderWorker<R: PackedRecord> {
+    files: Vec<PathBuf>,
+    options: DataLoaderOptions,
+    random: Random,
+    reservoir: Vec<R>,
+    reservoir_capacity: usize,
+    batch: Vec<R>,
+    sender: SyncSender<io::Result<Vec<R>>>,
+    filter: Option<PositionFilter>,
+    accepts: fn(&PositionFilter, &R) -> bool,
+}
+
+impl Default for DataLoaderOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 16384,
+            shuffle_buffer: 1 << 20,
+            interleaved_files: 8,
+            seed: 0,
+            epochs: 1,
+            skip_probability: 0.0,
+            threads: 1,
+            prefetch_batches: 4,
+        }
+    }
+}
+
+impl<R: PackedRecord + Send + 'static> DataLoader<R> {
+    pub fn new(files: Vec<PathBuf>, options: DataLoaderOptions) -> io::Result<Self> {
+        Self::start(files, options, None, |_, _| true)
+    }
+
+    //Leaves out the records the filter rejects. Filtering runs on the loader threads after
+    //skipping, so skipped records are never unpacked.
+    pub fn with_filter(
+        files: Vec<PathBuf>,
+        options: DataLoaderOptions,
+        filter: PositionFilter,
+    ) -> io::Result<Self>
+    where
+        R: FilterableRecord,
+    {
+        Self::start(files, options, Some(filter), PositionFilter::accepts_record)
+    }
+
+    fn start(
+        files: Vec<PathBuf>,
+        options: DataLoaderOptions,
+        filter: Option<PositionFilter>,
+        accepts: fn(&PositionFilter, &R) -> bool,
+    ) -> io::Result<Self> {
+        if files.is_empty() {
+            return Err(invalid_input("no files to load"));
+        }
+        if options.batch_size == 0 {
+            return Err(invalid_input("batch size has to be greater than 0"));
+        }
+        if !(0.0..1.0).contains(&options.skip_probability) {
+            return Err(invalid_input(format!(
+                "skip probability {} is outside of 0.0..1.0",
+                options.skip_probability
+            )));
+        }
+
+        let threads = options.threads.clamp(1, files.len());
+        let reservoir_capacity = (options.shuffle_buffer / threads).max(1);
+        let mut receivers = Vec::with_capacity(threads);
+        let mut workers = Vec::with_capacity(threads);
+        for thread_index in 0..threads {
+            let (sender, receiver) = sync_channel(options.prefetch_batches.max(1));
+            let worker = LoaderWorker {
+                files: files
+                    .iter()
+                    .skip(thread_index)
+                    .step_by(threads)
+                    .cloned()
+                    .collect(),
+                options: options.clone(),
+                random: Random::from_seed(
+                    options
+                        .seed
+                        .wrapping_add((thread_index as u64).wrapping_mul(0x9E3779B97F4A7C15)),
+                ),
+                reservoir: Vec::with_capacity(reservoir_capacity),
+                reservoir_capacity,
+                batch: Vec::with_capacity(options.batch_size),
+                sender,
+                filter: filter.clone(),
+                accepts,
+            };
+
+            receivers.push(receiver);
+            workers.push(thread::spawn(move || worker.run()));
+        }
+
+        Ok(Self {
+            receivers,
+            next_receiver: 0,
+            workers,
+        })
+    }
+}
+
+impl<R: PackedRecord + Send + 'static> Iterator for DataLoader<R> {
+    type Item = io::Result<Vec<R>>;
+
+    //The last batch of every thread can be smaller than the batch size
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.receivers.is_empty() {
+            let index = self.next_receiver % self.receivers.len();
+            match self.receivers[index].recv() {
+                Ok(batch) => {
+                    self.next_receiver = index + 1;
+                    return Some(batch);
+                }
+                Err(_) => {
+                    self.receivers.remove(index);
+                    self.next_receiver = index;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<R: PackedRecord + Send + 'static> Drop for DataLoader<R> {
+    fn drop(&mut self) {
+        //Workers stop on their next send once the receivers are gone
+        self.receivers.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<R: PackedRecord> LoaderWorker<R> {
+    fn run(mut self) {
+        if let Err(error) = self.load() {
+            let _ = self.sender.send(Err(error));
+        }
+    }
+
+    //Returns Ok when every epoch is done or the loader was dropped
+    fn load(&mut self) -> io::Result<()> {
+        let mut epoch = 0;
+        while self.options.epochs == 0 || epoch < self.options.epochs {
+            let mut files = self.files.clone();
+            self.random.shuffle(&mut files);
+
+            let mut records_read = 0u64;
+            let mut pending = files.into_iter();
+            let mut open: Vec<PackedReader<BufReader<File>, R>> = Vec::new();
+            loop {
+                while open.len() < self.options.interleaved_files.max(1) {
+                    let Some(path) = pending.next() else {
+                        break;
+                    };
+                    open.push(PackedReader::new(BufReader::new(File::open(path)?))?);
+                }
+                if open.is_empty() {
+                    break;
+                }
+
+                let index = self.random.next_range(open.len() as u64) as usize;
+                let Some(record) = open[index].read()? else {
+                    open.swap_remove(index);
+                    continue;
+                };
+
+                records_read += 1;
+                if self.options.skip_probability > 0.0
+                    && self.random.next_f64() < self.options.skip_probability
+                {
+                    continue;
+                }
+                if let Some(filter) = &self.filter {
+                    if !(self.accepts)(filter, &record) {
+                        continue;
+                    }
+                }
+                if !self.push_record(record) {
+                    return Ok(());
+                }
+            }
+
+            //Looping forever over files without records would never yield anything
+            if records_read == 0 {
+                break;
+            }
+            epoch += 1;
+        }
+
+        self.random.shuffle(&mut self.reservoir);
+        while let Some(record) = self.reservoir.pop() {
+            if !self.push_to_batch(record) {
+                return Ok(());
+            }
+        }
+        if !self.batch.is_empty() {
+            let batch = std::mem::take(&mut self.batch);
+            let _ = self.sender.send(Ok(batch));
+        }
+        Ok(())
+    }
+
+    //Fills the reservoir, then swaps every new record with a random one from it.
+    //False once the loader was dropped.
+    fn push_record(&mut self, record: R) -> bool {
+        if self.reservoir.len() < self.reservoir_capacity {
+            self.reservoir.push(record);
+            return true;
+        }
+
+        let index = self.random.next_range(self.reservoir.len() as u64) as usize;
+        let record = std::mem::replace(&mut self.reservoir[index], record);
+        self.push_to_batch(record)
+    }
+
+    fn push_to_batch(&mut self, record: R) -> bool {
+        self.batch.push(record);
+        if self.batch.len() < self.options.batch_size {
+            return true;
+        }
+
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(self.options.batch_size));
+        self.sender.send(Ok(batch)).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{DataLoader, DataLoaderOptions};
+    use crate::{binpacks::write_packed, ChessBoard, ChessBoardPacked, FEN};
+
+    const RECORDS_PER_FILE: usize = 1000;
+
+    //Files whose records carry their id, file index * RECORDS_PER_FILE + index, as the score
+    fn write_files(name: &str, file_count: usize) -> Vec<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("spear-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let board = ChessBoard::from_fen(&FEN::start_position());
+        (0..file_count)
+            .map(|file_index| {
+                let records: Vec<_> = (0..RECORDS_PER_FILE)
+                    .map(|index| {
+                        let id = file_index * RECORDS_PER_FILE + index;
+                        ChessBoardPacked::from_board(&board, (id as f32 + 0.5) / u16::MAX as f32)
+                    })
+                    .collect();
+                let path = dir.join(format!("{file_index}.bin"));
+                write_packed(fs::File::create(&path).unwrap(), &records, false).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn remove_files(files: &[PathBuf]) {
+        fs::remove_dir_all(files[0].parent().unwrap()).unwrap();
+    }
+
+    fn id(record: &ChessBoardPacked) -> usize {
+        let bytes = record.to_bytes();
+        u16::from_le_bytes([bytes[33], bytes[34]]) as usize
+    }
+
+    fn batches(files: &[PathBuf], options: &DataLoaderOptions) -> Vec<Vec<usize>> {
+        DataLoader::<ChessBoardPacked>::new(files.to_vec(), options.clone())
+            .unwrap()
+            .map(|batch| batch.unwrap().iter().map(id).collect())
+            .collect()
+    }
+
+    fn options(seed: u64) -> DataLoaderOptions {
+        DataLoaderOptions {
+            batch_size: 100,
+            shuffle_buffer: 256,
+            interleaved_files: 2,
+            seed,
+            threads: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn seed_decides_the_batches() {
+        let files = write_files("loader-seed", 3);
+        let first = batches(&files, &options(1));
+        let second = batches(&files, &options(1));
+        let other_seed = batches(&files, &options(2));
+        remove_files(&files);
+
+        assert!(first == second);
+        assert!(first != other_seed);
+        assert!(first.iter().all(|batch| batch.len() <= 100));
+
+        let mut ids: Vec<usize> = first.into_iter().flatten().collect();
+        ids.sort_unstable();
+        assert!(ids.into_iter().eq(0..3 * RECORDS_PER_FILE));
+    }
+
+    #[test]
+    fn every_epoch_yields_every_record() {
+        let files = write_files("loader-epochs", 2);
+        let options = DataLoaderOptions {
+            epochs: 3,
+            ..options(3)
+        };
+        let mut counts = vec![0; 2 * RECORDS_PER_FILE];
+        for id in batches(&files, &options).into_iter().flatten() {
+            counts[id] += 1;
+        }
+        remove_files(&files);
+
+        assert!(counts.iter().all(|&count| count == 3));
+    }
+
+    #[test]
+    fn skip_probability_drops_records() {
+        let files = write_files("loader-skip", 2);
+        let options = DataLoaderOptions {
+            skip_probability: 0.5,
+            ..options(4)
+        };
+        let mut ids: Vec<usize> = batches(&files, &options).into_iter().flatten().collect();
+        remove_files(&files);
+
+        let total = 2 * RECORDS_PER_FILE;
+        assert!(
+            (total * 2 / 5..total * 3 / 5).contains(&ids.len()),
+            "{}",
+            ids.len()
+        );
+        ids.sort_unstable();
+        ids.dedup();
+        assert!(ids.len() * 5 > total * 2);
+    }
+
+    #[test]
+    fn interleaved_files_are_mixed() {
+        let files = write_files("loader-interleave", 2);
+        //A single slot reservoir keeps the read order, so the first batch shows which files
+        //were open at the start
+        let single_file = DataLoaderOptions {
+            shuffle_buffer: 1,
+            interleaved_files: 1,
+            threads: 1,
+            ..options(5)
+        };
+        let interleaved = DataLoaderOptions {
+            interleaved_files: 2,
+            ..single_file.clone()
+        };
+        let first_file_count =
+            |batch: &Vec<usize>| batch.iter().filter(|&&id| id < RECORDS_PER_FILE).count();
+        let sequential = batches(&files, &single_file);
+        let mixed = batches(&files, &interleaved);
+        remove_files(&files);
+
+        assert!([0, 100].contains(&first_file_count(&sequential[0])));
+        assert!((20..80).contains(&first_file_count(&mixed[0])));
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::ChessBoard;
+
+use super::{
+    packed_io::{invalid_input, write_packed_file, PackedReader},
+    ChessBoardPacked,
+};
+
+#[derive(Clone)]
+pub struct DedupOptions {
+    //Records sorted in memory before they are spilled to a run file, 36 + 8 bytes each
+    pub run_size: usize,
+    //Directory of the temporary run files, needs room for a copy of the input
+    pub temp_dir: PathBuf,
+    pub checksum: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct DedupSummary {
+    pub input_records: u64,
+    pub unique_records: u64,
+    pub runs: usize,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            run_size: 1 << 24,
+            temp_dir: std::env::temp_dir(),
+            checksum: false,
+        }
+    }
+}
+
+//Run file entry: zobrist key u64 | packed record, sorted by key and then by board bytes
+const RUN_ENTRY_SIZE: usize = 8 + ChessBoardPacked::SIZE;
+//Compressed board and side to move, the part of the record identifying the position
+const POSITION_BYTES: usize = 33;
+
+type RunEntry = [u8; RUN_ENTRY_SIZE];
+
+//Numbers the calls of this process, so concurrent calls sharing a temp dir get distinct run files
+static DEDUP_CALLS: AtomicU64 = AtomicU64::new(0);
+
+//Removes the run files when deduplication finishes or fails
+struct RunFiles {
+    prefix: String,
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for RunFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+//Writes every position of the inputs once, keeping the first record in input order.
+//Positions are equal when pieces and side to move match, the zobrist key only orders them,
+//so key collisions never merge different positions. Inputs larger than memory are split into
+//sorted runs on disk, which are merged in a single pass.
+pub fn dedup_packed_files<P: AsRef<Path>>(
+    inputs: &[P],
+    output: &Path,
+    options: &DedupOptions,
+) -> io::Result<DedupSummary> {
+    if options.run_size == 0 {
+        return Err(invalid_input("run size has to be at least 1"));
+    }
+
+    let mut summary = DedupSummary::default();
+    let mut run_files = RunFiles {
+        prefix: format!(
+            "spear-dedup-{}-{}",
+            std::process::id(),
+            DEDUP_CALLS.fetch_add(1, Ordering::Relaxed)
+        ),
+        paths: Vec::new(),
+    };
+    let mut run = Vec::with_capacity(options.run_size.min(1 << 20));
+
+    for input in inputs {
+        let reader: PackedReader<_, ChessBoardPacked> =
+            PackedReader::new(BufReader::new(File::open(input)?))?;
+        for pack in reader {
+            run.push(run_entry(&pack?));
+            summary.input_records += 1;
+            if run.len() == options.run_size {
+                spill_run(&mut run, &mut run_files, options)?;
+            }
+        }
+    }
+    if !run.is_empty() {
+        spill_run(&mut run, &mut run_files, options)?;
+    }
+    summary.runs = run_files.paths.len();
+
+    let mut runs = run_files
+        .paths
+        .iter()
+        .map(|path| File::open(path).map(|file| BufReader::with_capacity(1 << 16, file)))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    //Equal positions pop in run order, and runs follow the input order
+    let mut heap = BinaryHeap::new();
+    for (index, run) in runs.iter_mut().enumerate() {
+        if let Some(entry) = read_run_entry(run)? {
+            heap.push(Reverse((sort_key(&entry), index, entry)));
+        }
+    }
+
+    summary.unique_records = write_packed_file(output, options.checksum, |writer| {
+        let mut last_position: Option<RunEntry> = None;
+        while let Some(Reverse((_, index, entry))) = heap.pop() {
+            if last_position.is_none_or(|last| !same_position(&last, &entry)) {
+                writer.write(&ChessBoardPacked::from_bytes(&entry[8..])?)?;
+                last_position = Some(entry);
+            }
+            if let Some(next) = read_run_entry(&mut runs[index])? {
+                heap.push(Reverse((sort_key(&next), index, next)));
+            }
+        }
+        Ok(())
+    })?;
+    Ok(summary)
+}
+
+fn run_entry(pack: &ChessBoardPacked) -> RunEntry {
+    let key = ChessBoard::from_board_pack(pack).get_key().get_raw();
+    let mut entry = [0; RUN_ENTRY_SIZE];
+    entry[..8].copy_from_slice(&key.to_be_bytes());
+    entry[8..].copy_from_slice(&pack.to_bytes());
+    entry
+}
+
+//Key and position, scores and results are left out so duplicates keep their input order
+#[inline]
+fn sort_key(entry: &RunEntry) -> [u8; 8 + POSITION_BYTES] {
+    entry[..8 + POSITION_BYTES].try_into().unwrap()
+}
+
+#[inline]
+fn same_position(a: &RunEntry, b: &RunEntry) -> bool {
+    sort_key(a) == sort_key(b)
+}
+
+//Sorts the run, drops duplicates within it and writes it to a new run file. The sort is
+//stable and the key is stored big endian, so byte order matches key order.
+fn spill_run(
+    run: &mut Vec<RunEntry>,
+    run_files: &mut RunFiles,
+    options: &DedupOptions,
+) -> io::Result<()> {
+    run.sort_by_key(sort_key);
+    run.dedup_by(|next, kept| same_position(kept, next));
+
+    let path = options.temp_dir.join(format!(
+        "{}-{}.run",
+        run_files.prefix,
+        run_files.paths.len()
+    ));
+    let file = File::create_new(&path)?;
+    run_files.paths.push(path);
+
+    let mut writer = BufWriter::new(file);
+    for entry in run.iter() {
+        writer.write_all(entry)?;
+    }
+    writer.flush()?;
+    run.clear();
+    Ok(())
+}
+
+fn read_run_entry<Rd: Read>(run: &mut Rd) -> io::Result<Option<RunEntry>> {
+    let mut entry = [0; RUN_ENTRY_SIZE];
+    match run.read_exact(&mut entry) {
+        Ok(()) => Ok(Some(entry)),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, thread};
+
+    use super::{dedup_packed_files, DedupOptions};
+    use crate::{
+        binpacks::{read_packed, write_packed},
+        ChessBoard, ChessBoardPacked, FEN,
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("spear-{name}-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    //Every position of a short game, each one written twice with different scores.
+    //Returns the bytes of the first copies, which deduplication has to keep.
+    fn write_input(path: &PathBuf, moves: &str) -> Vec<[u8; ChessBoardPacked::SIZE]> {
+        let mut board = ChessBoard::from_fen(&FEN::start_position());
+        let mut packs = Vec::new();
+        let mut first_copies = Vec::new();
+        for uci in moves.split(' ') {
+            packs.push(ChessBoardPacked::from_board(&board, 0.25));
+            packs.push(ChessBoardPacked::from_board(&board, 0.75));
+            first_copies.push(packs[packs.len() - 2].to_bytes());
+            board.apply_move(board.parse_uci_move(uci).unwrap());
+        }
+        write_packed(fs::File::create(path).unwrap(), &packs, false).unwrap();
+        first_copies.sort();
+        first_copies
+    }
+
+    #[test]
+    fn concurrent_calls_share_a_temp_dir() {
+        let dir = temp_dir("dedup-concurrent");
+        let games = ["e2e4 e7e5 g1f3 b8c6 f1b5", "d2d4 d7d5 c2c4 e7e6 b1c3 g8f6"];
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = games
+                .iter()
+                .enumerate()
+                .map(|(index, moves)| {
+                    let dir = &dir;
+                    scope.spawn(move || {
+                        let input = dir.join(format!("input-{index}.bin"));
+                        let output = dir.join(format!("output-{index}.bin"));
+                        let expected = write_input(&input, moves);
+                        let options = DedupOptions {
+                            run_size: 3,
+                            temp_dir: dir.clone(),
+                            checksum: true,
+                        };
+                        let summary = dedup_packed_files(&[input], &output, &options).unwrap();
+                        assert_eq!(summary.unique_records, expected.len() as u64);
+                        (output, expected)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        for (output, expected) in results {
+            let packs: Vec<ChessBoardPacked> =
+                read_packed(fs::File::open(output).unwrap()).unwrap();
+            let mut bytes: Vec<_> = packs.iter().map(ChessBoardPacked::to_bytes).collect();
+            bytes.sort();
+            assert_eq!(bytes, expected);
+        }
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["input-0.bin", "input-1.bin", "output-0.bin", "output-1.bin"]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_calls_leave_no_output() {
+        let dir = temp_dir("dedup-failed");
+        let input = dir.join("input.bin");
+        let output = dir.join("output.bin");
+        write_input(&input, "e2e4 e7e5");
+        fs::write(&input, &fs::read(&input).unwrap()[..40]).unwrap();
+
+        let options = DedupOptions {
+            temp_dir: dir.clone(),
+            ..Default::default()
+        };
+        assert!(dedup_packed_files(&[&input], &output, &options).is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
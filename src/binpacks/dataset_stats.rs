@@ -0,0 +1,302 @@
+use std::{
+    fmt::{Display, Formatter, Result},
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+};
+
+use crate::{base_structures::Side, ChessBoard};
+
+use super::{packed_io::PackedReader, ChessBoardPacked};
+
+//Summary of a ChessBoardPacked dataset. Statistics of several files or threads can be
+//combined with `merge`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DatasetStats {
+    pub positions: u64,
+    //Positions by game phase (ChessBoard::get_phase), promotions can push it above 24
+    pub phases: Vec<u64>,
+    //Positions by number of pieces on the board, kings included
+    pub piece_counts: [u64; 33],
+    //White and black to move
+    pub side_to_move: [u64; 2],
+    //Black wins, draws and white wins
+    pub results: [u64; 3],
+    //Side to move scores in SCORE_BINS equal buckets from 0.0 to 1.0
+    pub scores: [u64; DatasetStats::SCORE_BINS],
+    pub score_sum: f64,
+    pub in_check: u64,
+}
+
+impl Default for DatasetStats {
+    fn default() -> Self {
+        Self {
+            positions: 0,
+            phases: vec![0; 25],
+            piece_counts: [0; 33],
+            side_to_move: [0; 2],
+            results: [0; 3],
+            scores: [0; Self::SCORE_BINS],
+            score_sum: 0.0,
+            in_check: 0,
+        }
+    }
+}
+
+impl DatasetStats {
+    pub const SCORE_BINS: usize = 20;
+
+    pub fn push(&mut self, pack: &ChessBoardPacked) {
+        self.push_board(&ChessBoard::from_board_pack(pack), pack)
+    }
+
+    //Same as `push` for callers that already unpacked the board
+    pub fn push_board(&mut self, board: &ChessBoard, pack: &ChessBoardPacked) {
+        self.positions += 1;
+
+        let phase = board.get_phase() as usize;
+        if phase >= self.phases.len() {
+            self.phases.resize(phase + 1, 0);
+        }
+        self.phases[phase] += 1;
+
+        let piece_count = board.get_occupancy().pop_count() as usize;
+        self.piece_counts[piece_count.min(32)] += 1;
+        self.side_to_move[board.side_to_move().get_raw() as usize] += 1;
+        self.results[(pack.get_result().signum() + 1) as usize] += 1;
+
+        let score = pack.get_score();
+        self.scores[((score * Self::SCORE_BINS as f32) as usize).min(Self::SCORE_BINS - 1)] += 1;
+        self.score_sum += score as f64;
+        self.in_check += u64::from(board.is_side_to_move_in_check());
+    }
+
+    pub fn merge(&mut self, other: &DatasetStats) {
+        self.positions += other.positions;
+        if other.phases.len() > self.phases.len() {
+            self.phases.resize(other.phases.len(), 0);
+        }
+        add_counts(&mut self.phases, &other.phases);
+        add_counts(&mut self.piece_counts, &other.piece_counts);
+        add_counts(&mut self.side_to_move, &other.side_to_move);
+        add_counts(&mut self.results, &other.results);
+        add_counts(&mut self.scores, &other.scores);
+        self.score_sum += other.score_sum;
+        self.in_check += other.in_check;
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = PackedReader::new(BufReader::new(File::open(path)?))?;
+        let mut result = Self::default();
+        for pack in reader {
+            result.push(&pack?);
+        }
+        Ok(result)
+    }
+
+    #[inline]
+    pub fn in_check_ratio(&self) -> f64 {
+        self.share(self.in_check)
+    }
+
+    #[inline]
+    pub fn white_to_move_ratio(&self) -> f64 {
+        self.share(self.side_to_move[Side::WHITE.get_raw() as usize])
+    }
+
+    #[inline]
+    pub fn mean_score(&self) -> f64 {
+        if self.positions == 0 {
+            0.0
+        } else {
+            self.score_sum / self.positions as f64
+        }
+    }
+
+    #[inline]
+    fn share(&self, count: u64) -> f64 {
+        if self.positions == 0 {
+            0.0
+        } else {
+            count as f64 / self.positions as f64
+        }
+    }
+
+    //Histogram row with a bar scaled to the share of all positions
+    fn write_row(&self, formatter: &mut Formatter<'_>, label: &str, count: u64) -> Result {
+        let share = self.share(count);
+        let row = format!(
+            "  {label:>9} {count:>12} {:>6.2}% {}",
+            share * 100.0,
+            "#".repeat((share * 50.0).round() as usize)
+        );
+        writeln!(formatter, "{}", row.trim_end())
+    }
+}
+
+fn add_counts(counts: &mut [u64], other: &[u64]) {
+    for (count, other) in counts.iter_mut().zip(other) {
+        *count += other;
+    }
+}
+
+impl Display for DatasetStats {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        writeln!(formatter, "positions     {}", self.positions)?;
+        writeln!(
+            formatter,
+            "side to move  white {} ({:.2}%), black {} ({:.2}%)",
+            self.side_to_move[0],
+            self.share(self.side_to_move[0]) * 100.0,
+            self.side_to_move[1],
+            self.share(self.side_to_move[1]) * 100.0
+        )?;
+        writeln!(
+            formatter,
+            "results       white {} ({:.2}%), draw {} ({:.2}%), black {} ({:.2}%)",
+            self.results[2],
+            self.share(self.results[2]) * 100.0,
+            self.results[1],
+            self.share(self.results[1]) * 100.0,
+            self.results[0],
+            self.share(self.results[0]) * 100.0
+        )?;
+        writeln!(
+            formatter,
+            "in check      {} ({:.2}%)",
+            self.in_check,
+            self.in_check_ratio() * 100.0
+        )?;
+        writeln!(formatter, "mean score    {:.4}", self.mean_score())?;
+
+        writeln!(formatter, "phase")?;
+        for (phase, &count) in self.phases.iter().enumerate() {
+            if count > 0 {
+                self.write_row(formatter, &format!("{phase:>2}"), count)?;
+            }
+        }
+
+        writeln!(formatter, "pieces")?;
+        for (pieces, &count) in self.piece_counts.iter().enumerate() {
+            if count > 0 {
+                self.write_row(formatter, &format!("{pieces:>2}"), count)?;
+            }
+        }
+
+        writeln!(formatter, "score")?;
+        for (bin, &count) in self.scores.iter().enumerate() {
+            let low = bin as f32 / Self::SCORE_BINS as f32;
+            let high = (bin + 1) as f32 / Self::SCORE_BINS as f32;
+            self.write_row(formatter, &format!("{low:.2}-{high:.2}"), count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::DatasetStats;
+    use crate::{base_structures::Side, binpacks::write_packed, ChessBoard, ChessBoardPacked, FEN};
+
+    //(fen, score, winner), scores stay clear of the bin edges after u16 quantisation
+    const DATASET: [(&str, f32, Option<Side>); 4] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            0.52,
+            Some(Side::WHITE),
+        ),
+        ("4k3/8/8/8/8/8/4q3/4K3 w - - 0 1", 0.12, Some(Side::BLACK)),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1", 1.0, None),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/QQQQQQQQ/RNBQKBNR w KQkq - 0 1",
+            0.0,
+            None,
+        ),
+    ];
+
+    fn packs() -> Vec<ChessBoardPacked> {
+        DATASET
+            .iter()
+            .map(|&(fen, score, winner)| {
+                let board = ChessBoard::from_fen(&FEN::from_str(fen));
+                let mut pack = ChessBoardPacked::from_board(&board, score);
+                if let Some(winner) = winner {
+                    pack.apply_result(winner);
+                }
+                pack
+            })
+            .collect()
+    }
+
+    fn stats_of(packs: &[ChessBoardPacked]) -> DatasetStats {
+        let mut stats = DatasetStats::default();
+        for pack in packs {
+            stats.push(pack);
+        }
+        stats
+    }
+
+    #[test]
+    fn counts_a_known_dataset() {
+        let stats = stats_of(&packs());
+
+        assert_eq!(stats.positions, 4);
+
+        //Eight extra queens push the last phase to 56
+        assert_eq!(stats.phases.len(), 57);
+        assert_eq!(stats.phases[24], 1);
+        assert_eq!(stats.phases[4], 2);
+        assert_eq!(stats.phases[56], 1);
+        assert_eq!(stats.phases.iter().sum::<u64>(), 4);
+
+        assert_eq!(stats.piece_counts[32], 2);
+        assert_eq!(stats.piece_counts[10], 1);
+        assert_eq!(stats.piece_counts[3], 1);
+        assert_eq!(stats.piece_counts.iter().sum::<u64>(), 4);
+
+        assert_eq!(stats.side_to_move, [3, 1]);
+        assert_eq!(stats.results, [1, 2, 1]);
+
+        let mut scores = [0; DatasetStats::SCORE_BINS];
+        scores[0] = 1;
+        scores[2] = 1;
+        scores[10] = 1;
+        scores[19] = 1;
+        assert_eq!(stats.scores, scores);
+        assert!((stats.mean_score() - 0.41).abs() < 1e-4);
+
+        assert_eq!(stats.in_check, 1);
+        assert_eq!(stats.in_check_ratio(), 0.25);
+        assert_eq!(stats.white_to_move_ratio(), 0.75);
+    }
+
+    #[test]
+    fn merge_equals_a_single_pass() {
+        let packs = packs();
+        let single_pass = stats_of(&packs);
+
+        //The second half holds the highest phase, so merging has to grow the phase counts
+        let mut merged = stats_of(&packs[..2]);
+        merged.merge(&stats_of(&packs[2..]));
+        assert_eq!(merged, single_pass);
+
+        let mut merged = DatasetStats::default();
+        for pack in &packs {
+            merged.merge(&stats_of(std::slice::from_ref(pack)));
+        }
+        assert_eq!(merged, single_pass);
+    }
+
+    #[test]
+    fn reads_stats_from_file() {
+        let path = std::env::temp_dir().join(format!("spear-stats-{}.bin", std::process::id()));
+        write_packed(fs::File::create(&path).unwrap(), &packs(), true).unwrap();
+        let stats = DatasetStats::from_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(stats.unwrap(), stats_of(&packs()));
+        assert_eq!(DatasetStats::default().mean_score(), 0.0);
+    }
+}
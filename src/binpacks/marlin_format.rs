@@ -0,0 +1,265 @@
+use std::io;
+
+use crate::{Bitboard, CastleRights, ChessBoard, Piece, Side, Square};
+
+use super::{
+    packed_io::{invalid_data, invalid_input, PackedRecord},
+    GameResult,
+};
+
+//32 byte marlinformat record used by marlinflow and bullet, all values little endian:
+//occupancy u64 | 32 piece nibbles in occupancy order | stm/ep u8 | half moves u8 |
+//full moves u16 | eval i16 | wdl u8 | extra u8
+//A piece nibble holds the piece type, 6 for a rook that can still castle, and bit 3 for black.
+//The stm/ep byte holds the en passant square (64 if none) and bit 7 for black to move.
+//Eval is from white's point of view, wdl is 0 for a black win, 1 for a draw, 2 for a white win.
+#[derive(Clone, Copy, PartialEq)]
+pub struct MarlinFormat {
+    occupancy: Bitboard,
+    pieces: [u8; 16],
+    stm_ep: u8,
+    half_moves: u8,
+    full_moves: u16,
+    eval: i16,
+    wdl: u8,
+    extra: u8,
+}
+
+const UNMOVED_ROOK: u8 = 6;
+const BLACK_FLAG: u8 = 8;
+
+impl MarlinFormat {
+    pub const SIZE: usize = 32;
+
+    //Unknown results are stored as draws, the format has no value for them.
+    //Fails for boards with more than 32 pieces, which do not fit into the piece nibbles.
+    pub fn from_board(board: &ChessBoard, white_eval: i16, result: GameResult) -> io::Result<Self> {
+        if board.get_occupancy().pop_count() > 32 {
+            return Err(invalid_input("more than 32 pieces"));
+        }
+
+        let mut pieces = [0u8; 16];
+        let mut index = 0;
+        board.get_occupancy().map(|square| {
+            let side = board.get_piece_color_on_square(square);
+            let mut nibble = board.get_piece_on_square(square).get_raw();
+            if nibble == Piece::ROOK.get_raw()
+                && castle_right_for_rook(square, side)
+                    .is_some_and(|right| board.castle_rights().has_right(right))
+            {
+                nibble = UNMOVED_ROOK;
+            }
+            if side == Side::BLACK {
+                nibble |= BLACK_FLAG;
+            }
+            pieces[index / 2] |= nibble << (4 * (index % 2));
+            index += 1;
+        });
+
+        Ok(Self {
+            occupancy: board.get_occupancy(),
+            pieces,
+            stm_ep: (board.side_to_move().get_raw() << 7) | board.en_passant_square().get_raw(),
+            half_moves: board.half_move_counter(),
+            full_moves: board.full_move_counter(),
+            eval: white_eval,
+            wdl: match result {
+                GameResult::BlackWin => 0,
+                GameResult::WhiteWin => 2,
+                GameResult::Draw | GameResult::Unknown => 1,
+            },
+            extra: 0,
+        })
+    }
+
+    pub fn to_board(&self) -> ChessBoard {
+        let mut board = ChessBoard::default();
+        let mut castle_rights = CastleRights::NULL;
+        let mut index = 0;
+        self.occupancy.map(|square| {
+            let nibble = self.piece_nibble(index);
+            index += 1;
+
+            let side = if nibble & BLACK_FLAG > 0 {
+                Side::BLACK
+            } else {
+                Side::WHITE
+            };
+            let mut piece = Piece::from_raw(nibble & 7);
+            if piece.get_raw() == UNMOVED_ROOK {
+                piece = Piece::ROOK;
+                if let Some(right) = castle_right_for_rook(square, side) {
+                    castle_rights.set_right(right);
+                }
+            }
+
+            if side == Side::WHITE {
+                board.set_piece_on_square::<true>(square, piece);
+            } else {
+                board.set_piece_on_square::<false>(square, piece);
+            }
+        });
+
+        board.set_side_to_move(self.get_side_to_move());
+        board.set_castle_rights(castle_rights);
+        board.set_en_passant_square(Square::from_raw(self.stm_ep & 0x7F));
+        board.set_move_counters(self.half_moves, self.full_moves);
+        board.refresh_phase();
+        board
+    }
+
+    #[inline]
+    pub fn get_side_to_move(&self) -> Side {
+        Side::from_raw(self.stm_ep >> 7)
+    }
+
+    #[inline]
+    pub fn get_white_perspective_eval(&self) -> i16 {
+        self.eval
+    }
+
+    #[inline]
+    pub fn get_result(&self) -> GameResult {
+        [GameResult::BlackWin, GameResult::Draw, GameResult::WhiteWin][self.wdl as usize]
+    }
+
+    #[inline]
+    pub fn get_extra(&self) -> u8 {
+        self.extra
+    }
+
+    #[inline]
+    pub fn set_extra(&mut self, extra: u8) {
+        self.extra = extra
+    }
+
+    pub fn to_bytes(&self) -> [u8; MarlinFormat::SIZE] {
+        let mut bytes = [0; MarlinFormat::SIZE];
+        bytes[0..8].copy_from_slice(&self.occupancy.get_raw().to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces);
+        bytes[24] = self.stm_ep;
+        bytes[25] = self.half_moves;
+        bytes[26..28].copy_from_slice(&self.full_moves.to_le_bytes());
+        bytes[28..30].copy_from_slice(&self.eval.to_le_bytes());
+        bytes[30] = self.wdl;
+        bytes[31] = self.extra;
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(invalid_data("marlinformat record is too short"));
+        }
+
+        let result = Self {
+            occupancy: Bitboard::from_raw(u64::from_le_bytes(bytes[0..8].try_into().unwrap())),
+            pieces: bytes[8..24].try_into().unwrap(),
+            stm_ep: bytes[24],
+            half_moves: bytes[25],
+            full_moves: u16::from_le_bytes([bytes[26], bytes[27]]),
+            eval: i16::from_le_bytes([bytes[28], bytes[29]]),
+            wdl: bytes[30],
+            extra: bytes[31],
+        };
+
+        if result.occupancy.pop_count() > 32 {
+            return Err(invalid_data("more than 32 pieces"));
+        }
+        if result.wdl > 2 {
+            return Err(invalid_data(format!("invalid wdl {}", result.wdl)));
+        }
+        if result.stm_ep & 0x7F > Square::NULL.get_raw() {
+            return Err(invalid_data(format!(
+                "invalid en passant square {}",
+                result.stm_ep & 0x7F
+            )));
+        }
+
+        let mut index = 0;
+        let mut valid = true;
+        result.occupancy.map(|square| {
+            let nibble = result.piece_nibble(index);
+            index += 1;
+            let side = if nibble & BLACK_FLAG > 0 {
+                Side::BLACK
+            } else {
+                Side::WHITE
+            };
+            valid &= match nibble & 7 {
+                UNMOVED_ROOK => castle_right_for_rook(square, side).is_some(),
+                piece => piece <= Piece::KING.get_raw(),
+            };
+        });
+        if !valid {
+            return Err(invalid_data("invalid piece nibble"));
+        }
+
+        Ok(result)
+    }
+
+    #[inline]
+    fn piece_nibble(&self, index: usize) -> u8 {
+        (self.pieces[index / 2] >> (4 * (index % 2))) & 0xF
+    }
+}
+
+impl PackedRecord for MarlinFormat {
+    const SIZE: usize = MarlinFormat::SIZE;
+    const KIND: u8 = 3;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_bytes())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+//Castle right kept alive by a rook on its initial square
+pub(super) fn castle_right_for_rook(square: Square, side: Side) -> Option<CastleRights> {
+    match (square, side) {
+        (Square::A1, Side::WHITE) => Some(CastleRights::WHITE_QUEEN),
+        (Square::H1, Side::WHITE) => Some(CastleRights::WHITE_KING),
+        (Square::A8, Side::BLACK) => Some(CastleRights::BLACK_QUEEN),
+        (Square::H8, Side::BLACK) => Some(CastleRights::BLACK_KING),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MarlinFormat;
+    use crate::{ChessBoard, GameResult, FEN};
+
+    const FENS: [&str; 5] = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+        "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 37 112",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+
+    #[test]
+    fn round_trip_keeps_castle_rights_and_en_passant() {
+        for fen in FENS {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            let record = MarlinFormat::from_board(&board, -35, GameResult::WhiteWin).unwrap();
+            let record = MarlinFormat::from_bytes(&record.to_bytes()).unwrap();
+            let result = record.to_board();
+
+            assert_eq!(result.get_fen().to_string(), board.get_fen().to_string());
+            assert_eq!(result.get_key().get_raw(), board.get_key().get_raw());
+            assert_eq!(record.get_white_perspective_eval(), -35);
+            assert!(record.get_result() == GameResult::WhiteWin);
+        }
+    }
+
+    #[test]
+    fn rejects_more_than_32_pieces() {
+        let board = ChessBoard::from_fen(&FEN::from_str(
+            "rnbqkbnr/pppppppp/8/8/8/P7/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ));
+        assert!(MarlinFormat::from_board(&board, 0, GameResult::Draw).is_err());
+    }
+}
@@ -1,5 +1,31 @@
 mod board_pack;
+mod board_record;
+mod bullet_format;
+mod data_loader;
+mod dataset_dedup;
+mod dataset_stats;
+mod marlin_format;
+mod packed_io;
 mod policy_pack;
+mod policy_record;
+mod position_filter;
+mod stockfish_binpack;
 
 pub use board_pack::ChessBoardPacked;
-pub use policy_pack::PolicyPacked;
+pub use board_record::{ChessBoardRecord, GameResult};
+pub use bullet_format::BulletFormat;
+pub use data_loader::{DataLoader, DataLoaderOptions};
+pub use dataset_dedup::{dedup_packed_files, DedupOptions, DedupSummary};
+pub use dataset_stats::DatasetStats;
+pub use marlin_format::MarlinFormat;
+pub use packed_io::{
+    read_packed, read_raw_records, write_packed, write_packed_file, write_raw_records,
+    PackedHeader, PackedReader, PackedRecord, PackedWriter,
+};
+pub use policy_pack::{PolicyMoveData, PolicyPacked};
+pub use policy_record::PolicyRecord;
+pub use position_filter::{FilterInput, FilterableRecord, PositionFilter};
+pub use stockfish_binpack::{
+    read_stockfish_binpack, write_stockfish_binpack, BinpackEntry, StockfishBinpackReader,
+    StockfishBinpackWriter,
+};
@@ -0,0 +1,420 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+//Record with an explicit little endian byte layout. Most records have a fixed size, variable
+//length records start with a fixed part of SIZE bytes that tells how many bytes follow it.
+pub trait PackedRecord: Sized {
+    const SIZE: usize;
+    //Distinguishes record types sharing the same file header
+    const KIND: u8;
+
+    //The slice is byte_size() bytes long
+    fn write_bytes(&self, bytes: &mut [u8]);
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self>;
+
+    #[inline]
+    fn byte_size(&self) -> usize {
+        Self::SIZE
+    }
+
+    //Bytes of the record following its first SIZE bytes
+    #[inline]
+    fn remaining_size(_fixed_part: &[u8]) -> usize {
+        0
+    }
+}
+
+//File layout, all values little endian:
+//magic "SPPK" | version u16 | record kind u8 | flags u8 | record count u64 | records
+//When the checksum flag is set, a CRC-32 (IEEE) of all record bytes follows the records.
+#[derive(Clone, Copy, PartialEq)]
+pub struct PackedHeader {
+    pub version: u16,
+    pub kind: u8,
+    pub checksum: bool,
+    pub count: u64,
+}
+
+pub struct PackedWriter<W: Write, R: PackedRecord> {
+    inner: W,
+    header: PackedHeader,
+    written: u64,
+    written_bytes: u64,
+    crc: u32,
+    buffer: Vec<u8>,
+    record: PhantomData<R>,
+}
+
+pub struct PackedReader<Rd: Read, R: PackedRecord> {
+    inner: Rd,
+    header: PackedHeader,
+    read: u64,
+    crc: u32,
+    buffer: Vec<u8>,
+    finished: bool,
+    record: PhantomData<R>,
+}
+
+impl PackedHeader {
+    pub const MAGIC: [u8; 4] = *b"SPPK";
+    pub const VERSION: u16 = 1;
+    pub const SIZE: usize = 16;
+
+    const CHECKSUM_FLAG: u8 = 1;
+
+    pub fn to_bytes(&self) -> [u8; PackedHeader::SIZE] {
+        let mut bytes = [0; PackedHeader::SIZE];
+        bytes[0..4].copy_from_slice(&Self::MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6] = self.kind;
+        bytes[7] = if self.checksum {
+            Self::CHECKSUM_FLAG
+        } else {
+            0
+        };
+        bytes[8..16].copy_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; PackedHeader::SIZE]) -> io::Result<Self> {
+        if bytes[0..4] != Self::MAGIC {
+            return Err(invalid_data("not a packed record file"));
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version > Self::VERSION {
+            return Err(invalid_data(format!(
+                "unsupported packed record version {version}"
+            )));
+        }
+
+        Ok(Self {
+            version,
+            kind: bytes[6],
+            checksum: bytes[7] & Self::CHECKSUM_FLAG > 0,
+            count: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+        })
+    }
+}
+
+impl<W: Write, R: PackedRecord> PackedWriter<W, R> {
+    //The record count is part of the header, so it has to be known up front.
+    //`finish` fails when a different number of records was written.
+    pub fn new(mut inner: W, count: u64, checksum: bool) -> io::Result<Self> {
+        let header = PackedHeader {
+            version: PackedHeader::VERSION,
+            kind: R::KIND,
+            checksum,
+            count,
+        };
+        inner.write_all(&header.to_bytes())?;
+
+        Ok(Self {
+            inner,
+            header,
+            written: 0,
+            written_bytes: 0,
+            crc: CRC_INITIAL,
+            buffer: vec![0; R::SIZE],
+            record: PhantomData,
+        })
+    }
+
+    pub fn write(&mut self, record: &R) -> io::Result<()> {
+        if self.written == self.header.count {
+            return Err(invalid_input("more records than announced in the header"));
+        }
+
+        self.buffer.resize(record.byte_size(), 0);
+        record.write_bytes(&mut self.buffer);
+        self.inner.write_all(&self.buffer)?;
+        self.crc = crc32_update(self.crc, &self.buffer);
+        self.written += 1;
+        self.written_bytes += self.buffer.len() as u64;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.written != self.header.count {
+            return Err(invalid_input(format!(
+                "{} records written, {} announced in the header",
+                self.written, self.header.count
+            )));
+        }
+
+        if self.header.checksum {
+            self.inner.write_all(&(!self.crc).to_le_bytes())?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write + Seek, R: PackedRecord> PackedWriter<W, R> {
+    //Writer for outputs whose record count is only known at the end, like filtered or
+    //deduplicated datasets. `finish_unbounded` seeks back and patches the header.
+    pub fn new_unbounded(inner: W, checksum: bool) -> io::Result<Self> {
+        Self::new(inner, u64::MAX, checksum)
+    }
+
+    pub fn finish_unbounded(mut self) -> io::Result<W> {
+        self.header.count = self.written;
+        if self.header.checksum {
+            self.inner.write_all(&(!self.crc).to_le_bytes())?;
+        }
+
+        let end = self.inner.stream_position()?;
+        let record_bytes = PackedHeader::SIZE as u64
+            + self.written_bytes
+            + if self.header.checksum { 4 } else { 0 };
+        self.inner.seek(SeekFrom::Current(-(record_bytes as i64)))?;
+        self.inner.write_all(&self.header.to_bytes())?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    #[inline]
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl<Rd: Read, R: PackedRecord> PackedReader<Rd, R> {
+    pub fn new(mut inner: Rd) -> io::Result<Self> {
+        let mut header_bytes = [0; PackedHeader::SIZE];
+        inner.read_exact(&mut header_bytes)?;
+        let header = PackedHeader::from_bytes(&header_bytes)?;
+        if header.kind != R::KIND {
+            return Err(invalid_data(format!(
+                "file holds record kind {}, expected {}",
+                header.kind,
+                R::KIND
+            )));
+        }
+
+        Ok(Self {
+            inner,
+            header,
+            read: 0,
+            crc: CRC_INITIAL,
+            buffer: vec![0; R::SIZE],
+            finished: false,
+            record: PhantomData,
+        })
+    }
+
+    #[inline]
+    pub fn header(&self) -> PackedHeader {
+        self.header
+    }
+
+    //Reads the next record, None after the last one. The checksum is verified
+    //when the last record is read.
+    pub fn read(&mut self) -> io::Result<Option<R>> {
+        if self.read == self.header.count {
+            self.verify_checksum()?;
+            return Ok(None);
+        }
+
+        self.buffer.resize(R::SIZE, 0);
+        self.inner.read_exact(&mut self.buffer)?;
+        let remaining = R::remaining_size(&self.buffer);
+        if remaining > 0 {
+            self.buffer.resize(R::SIZE + remaining, 0);
+            self.inner.read_exact(&mut self.buffer[R::SIZE..])?;
+        }
+        self.crc = crc32_update(self.crc, &self.buffer);
+        self.read += 1;
+        let record = R::read_bytes(&self.buffer)?;
+        if self.read == self.header.count {
+            self.verify_checksum()?;
+        }
+        Ok(Some(record))
+    }
+
+    fn verify_checksum(&mut self) -> io::Result<()> {
+        if !self.header.checksum || self.finished {
+            return Ok(());
+        }
+
+        self.finished = true;
+        let mut checksum = [0; 4];
+        self.inner.read_exact(&mut checksum)?;
+        if u32::from_le_bytes(checksum) != !self.crc {
+            return Err(invalid_data("checksum mismatch"));
+        }
+        Ok(())
+    }
+}
+
+impl<Rd: Read, R: PackedRecord> Iterator for PackedReader<Rd, R> {
+    type Item = io::Result<R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+//Writes all records into a single file with header
+pub fn write_packed<W: Write, R: PackedRecord>(
+    inner: W,
+    records: &[R],
+    checksum: bool,
+) -> io::Result<W> {
+    let mut writer = PackedWriter::new(inner, records.len() as u64, checksum)?;
+    for record in records {
+        writer.write(record)?;
+    }
+    writer.finish()
+}
+
+pub fn read_packed<Rd: Read, R: PackedRecord>(inner: Rd) -> io::Result<Vec<R>> {
+    PackedReader::new(inner)?.collect()
+}
+
+//Creates a packed file whose record count is only known at the end. Records are written to a
+//temporary file next to the path, which replaces the path once the method and the header
+//patching succeeded, so a failure never leaves a file with an unpatched header behind.
+//Returns the number of records written.
+pub fn write_packed_file<R, F>(path: &Path, checksum: bool, method: F) -> io::Result<u64>
+where
+    R: PackedRecord,
+    F: FnOnce(&mut PackedWriter<BufWriter<File>, R>) -> io::Result<()>,
+{
+    let Some(file_name) = path.file_name() else {
+        return Err(invalid_input(format!(
+            "{} is not a file path",
+            path.display()
+        )));
+    };
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::create(&temp_path).and_then(|file| {
+        let mut writer = PackedWriter::new_unbounded(BufWriter::new(file), checksum)?;
+        method(&mut writer)?;
+        let written = writer.written();
+        writer.finish_unbounded()?.into_inner()?.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(written)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+//Writes records back to back without header, the layout community trainers expect
+pub fn write_raw_records<W: Write, R: PackedRecord>(mut inner: W, records: &[R]) -> io::Result<W> {
+    let mut buffer = Vec::new();
+    for record in records {
+        buffer.resize(record.byte_size(), 0);
+        record.write_bytes(&mut buffer);
+        inner.write_all(&buffer)?;
+    }
+    inner.flush()?;
+    Ok(inner)
+}
+
+//Reads headerless records until the end of the input
+pub fn read_raw_records<Rd: Read, R: PackedRecord>(mut inner: Rd) -> io::Result<Vec<R>> {
+    let mut bytes = Vec::new();
+    inner.read_to_end(&mut bytes)?;
+
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let Some(fixed_part) = bytes.get(offset..offset + R::SIZE) else {
+            return Err(invalid_data("input ends inside a record"));
+        };
+        let end = offset + R::SIZE + R::remaining_size(fixed_part);
+        let Some(record_bytes) = bytes.get(offset..end) else {
+            return Err(invalid_data("input ends inside a record"));
+        };
+        records.push(R::read_bytes(record_bytes)?);
+        offset = end;
+    }
+    Ok(records)
+}
+
+pub(super) fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    error: E,
+) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+pub(super) fn invalid_input<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    error: E,
+) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+const CRC_INITIAL: u32 = 0xFFFFFFFF;
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 > 0 {
+                0xEDB88320 ^ (value >> 1)
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+};
+
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{invalid_data, read_packed, write_packed_file};
+    use crate::{ChessBoard, ChessBoardPacked, FEN};
+
+    #[test]
+    fn packed_files_are_replaced_only_on_success() {
+        let dir = std::env::temp_dir().join(format!("spear-packed-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("output.bin");
+        let pack = ChessBoardPacked::from_board(&ChessBoard::from_fen(&FEN::start_position()), 0.5);
+
+        let written = write_packed_file(&path, true, |writer| {
+            writer.write(&pack)?;
+            writer.write(&pack)
+        })
+        .unwrap();
+        assert_eq!(written, 2);
+
+        let failed = write_packed_file(&path, false, |writer| {
+            writer.write(&pack)?;
+            Err(invalid_data("interrupted"))
+        });
+        assert!(failed.is_err());
+
+        let packs: Vec<ChessBoardPacked> = read_packed(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(packs.len(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,5 +1,12 @@
+use std::io;
+
 use crate::{Bitboard, ChessBoard, Move, Side};
 
+use super::{
+    board_pack::{board_to_compressed, read_compressed_board, read_side, write_compressed_board},
+    packed_io::{invalid_data, PackedRecord},
+};
+
 #[derive(Clone, Copy)]
 pub struct PolicyPacked {
     board: [Bitboard; 4],
@@ -8,7 +15,7 @@ pub struct PolicyPacked {
     moves: [PolicyMoveData; PolicyPacked::MAX_MOVE_COUNT],
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq)]
 pub struct PolicyMoveData {
     pub mv: Move,
     pub visits: u16,
@@ -27,6 +34,9 @@ impl Default for PolicyPacked {
 
 impl PolicyPacked {
     pub const MAX_MOVE_COUNT: usize = 101;
+    //4 compressed bitboards (u64) | side to move u8 | move count u8 |
+    //MAX_MOVE_COUNT x (move u16 | visits u16), little endian, unused move slots are zero
+    pub const SIZE: usize = 34 + PolicyPacked::MAX_MOVE_COUNT * 4;
 
     pub fn from_board(board: &ChessBoard) -> Self {
         Self {
@@ -57,32 +67,149 @@ impl PolicyPacked {
         &self.moves
     }
 
+    //Returns false and drops the move when all MAX_MOVE_COUNT slots are taken,
+    //use `PolicyRecord` for positions with more moves
     #[inline]
-    pub fn push_move(&mut self, mv: Move, visits: u16) {
-        self.moves[self.move_count() as usize] = PolicyMoveData { mv, visits };
+    #[must_use]
+    pub fn push_move(&mut self, mv: Move, visits: u16) -> bool {
+        let Some(slot) = self.moves.get_mut(self.move_count as usize) else {
+            return false;
+        };
+
+        *slot = PolicyMoveData { mv, visits };
         self.move_count += 1;
+        true
+    }
+
+    pub fn to_bytes(&self) -> [u8; PolicyPacked::SIZE] {
+        let mut bytes = [0; PolicyPacked::SIZE];
+        write_compressed_board(&self.board, &mut bytes[0..32]);
+        bytes[32] = self.side_to_move.get_raw();
+        bytes[33] = self.move_count;
+        let moves = &self.moves[..self.move_count as usize];
+        for (chunk, data) in bytes[34..].chunks_exact_mut(4).zip(moves) {
+            chunk[0..2].copy_from_slice(&data.mv.get_raw().to_le_bytes());
+            chunk[2..4].copy_from_slice(&data.visits.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(invalid_data("policy record is too short"));
+        }
+
+        let move_count = bytes[33];
+        if move_count as usize > Self::MAX_MOVE_COUNT {
+            return Err(invalid_data(format!("invalid move count {move_count}")));
+        }
+
+        let mut result = Self {
+            board: read_compressed_board(&bytes[0..32])?,
+            side_to_move: read_side(bytes[32])?,
+            ..Default::default()
+        };
+        for (slot, chunk) in result
+            .moves
+            .iter_mut()
+            .zip(bytes[34..].chunks_exact(4))
+            .take(move_count as usize)
+        {
+            *slot = PolicyMoveData {
+                mv: Move::from_raw(u16::from_le_bytes([chunk[0], chunk[1]])),
+                visits: u16::from_le_bytes([chunk[2], chunk[3]]),
+            };
+        }
+        result.move_count = move_count;
+        Ok(result)
     }
 }
 
-fn board_to_compressed(board: &ChessBoard) -> [Bitboard; 4] {
-    let mut result = [Bitboard::EMPTY; 4];
-
-    board.get_occupancy().map(|square| {
-        result[0].pop_bit(square);
-        result[1].pop_bit(square);
-        result[2].pop_bit(square);
-        result[3].pop_bit(square);
-        let piece = board.get_piece_on_square(square);
-        let color = board.get_piece_color_on_square(square);
-        for (bit_index, result_bit) in result.iter_mut().enumerate().take(3usize) {
-            if (piece.get_raw() & 1 << bit_index) > 0 {
-                result_bit.set_bit(square);
-            }
+impl PackedRecord for PolicyPacked {
+    const SIZE: usize = PolicyPacked::SIZE;
+    const KIND: u8 = 1;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_bytes())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PolicyPacked;
+    use crate::{
+        binpacks::{read_packed, write_packed, PackedHeader},
+        ChessBoard, ChessBoardPacked, FEN,
+    };
+
+    fn policy(fen: &str) -> PolicyPacked {
+        let board = ChessBoard::from_fen(&FEN::from_str(fen));
+        let mut policy = PolicyPacked::from_board(&board);
+        for (index, mv) in board.get_legal_moves().into_iter().enumerate() {
+            assert!(policy.push_move(mv, index as u16 * 1000));
         }
-        if color == Side::BLACK {
-            result[3].set_bit(square);
+        policy
+    }
+
+    #[test]
+    fn round_trip_keeps_board_and_moves() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b - - 0 1";
+        let policy = policy(fen);
+        let bytes = policy.to_bytes();
+        let result = PolicyPacked::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            ChessBoard::from_policy_pack(&result).get_fen().to_string(),
+            fen
+        );
+        assert_eq!(result.move_count(), policy.move_count());
+        assert!(result.moves() == policy.moves());
+        assert!(result.to_bytes() == bytes);
+    }
+
+    #[test]
+    fn rejects_full_move_lists_and_bad_move_counts() {
+        let mut policy = policy("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mv = policy.moves()[0].mv;
+        while (policy.move_count() as usize) < PolicyPacked::MAX_MOVE_COUNT {
+            assert!(policy.push_move(mv, 1));
         }
-    });
+        assert!(!policy.push_move(mv, 1));
+
+        let mut bytes = policy.to_bytes();
+        bytes[33] = PolicyPacked::MAX_MOVE_COUNT as u8 + 1;
+        assert!(PolicyPacked::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn packed_files_check_header_and_checksum() {
+        let policies = [
+            policy("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            policy("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+        ];
+        let bytes = write_packed(Vec::new(), &policies, true).unwrap();
+        let result: Vec<PolicyPacked> = read_packed(bytes.as_slice()).unwrap();
+        assert!(result
+            .iter()
+            .map(PolicyPacked::to_bytes)
+            .eq(policies.iter().map(PolicyPacked::to_bytes)));
 
-    result
+        let mut newer_version = bytes.clone();
+        newer_version[4..6].copy_from_slice(&(PackedHeader::VERSION + 1).to_le_bytes());
+        assert!(read_packed::<_, PolicyPacked>(newer_version.as_slice()).is_err());
+
+        assert!(read_packed::<_, ChessBoardPacked>(bytes.as_slice()).is_err());
+
+        //Visit count of the first move of the second record
+        let mut corrupt = bytes.clone();
+        corrupt[PackedHeader::SIZE + PolicyPacked::SIZE + 36] ^= 1;
+        let error = read_packed::<_, PolicyPacked>(corrupt.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "checksum mismatch");
+    }
 }
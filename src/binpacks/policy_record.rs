@@ -0,0 +1,277 @@
+use std::io::{self, Read, Write};
+
+use crate::{ChessBoard, Move};
+
+use super::{
+    packed_io::{invalid_data, PackedRecord},
+    ChessBoardRecord, GameResult, PolicyMoveData, PolicyPacked,
+};
+
+//Policy record holding only the moves that were searched, unlike the fixed PolicyPacked layout.
+//Layout, little endian: board record (ChessBoardRecord::SIZE bytes) | move count u8 |
+//move count x (move u16 | visits u16)
+//Packed files hold these records back to back after the header, see `PackedRecord`.
+#[derive(Clone, PartialEq)]
+pub struct PolicyRecord {
+    pub position: ChessBoardRecord,
+    moves: Vec<PolicyMoveData>,
+}
+
+impl PolicyRecord {
+    //Limited by the u8 move count, legal positions have at most 218 moves
+    pub const MAX_MOVE_COUNT: usize = u8::MAX as usize;
+    pub const HEADER_SIZE: usize = ChessBoardRecord::SIZE + 1;
+
+    pub fn from_board(board: &ChessBoard, eval: i16, result: GameResult) -> Self {
+        Self {
+            position: ChessBoardRecord::from_board(board, eval, result),
+            moves: Vec::new(),
+        }
+    }
+
+    //The fixed layout stores neither castle rights nor en passant, so they stay empty
+    pub fn from_policy_pack(pack: &PolicyPacked) -> Self {
+        let mut result =
+            Self::from_board(&ChessBoard::from_policy_pack(pack), 0, GameResult::Unknown);
+        result
+            .moves
+            .extend_from_slice(&pack.moves()[..pack.move_count() as usize]);
+        result
+    }
+
+    #[inline]
+    pub fn to_board(&self) -> ChessBoard {
+        self.position.to_board()
+    }
+
+    #[inline]
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    #[inline]
+    pub fn moves(&self) -> &[PolicyMoveData] {
+        &self.moves
+    }
+
+    //Returns false and drops the move when MAX_MOVE_COUNT moves are already stored
+    #[inline]
+    #[must_use]
+    pub fn push_move(&mut self, mv: Move, visits: u16) -> bool {
+        if self.moves.len() == Self::MAX_MOVE_COUNT {
+            return false;
+        }
+
+        self.moves.push(PolicyMoveData { mv, visits });
+        true
+    }
+
+    //Replaces the moves with raw visit counts scaled down to fit u16. Visit counts keep their
+    //proportions, and a visited move never rounds down to 0 visits.
+    //Returns false and keeps the moves when there are more than MAX_MOVE_COUNT of them.
+    #[must_use]
+    pub fn set_visit_counts(&mut self, visits: &[(Move, u64)]) -> bool {
+        if visits.len() > Self::MAX_MOVE_COUNT {
+            return false;
+        }
+
+        let max_visits = visits.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        self.moves = visits
+            .iter()
+            .map(|&(mv, count)| PolicyMoveData {
+                mv,
+                visits: quantize_visits(count, max_visits),
+            })
+            .collect();
+        true
+    }
+
+    //Share of the visits of every move, empty when no move was visited
+    pub fn visit_distribution(&self) -> Vec<(Move, f32)> {
+        let total = self
+            .moves
+            .iter()
+            .map(|data| data.visits as u64)
+            .sum::<u64>();
+        if total == 0 {
+            return Vec::new();
+        }
+
+        self.moves
+            .iter()
+            .map(|data| (data.mv, data.visits as f32 / total as f32))
+            .collect()
+    }
+
+    #[inline]
+    pub fn byte_size(&self) -> usize {
+        Self::HEADER_SIZE + self.moves.len() * 4
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.byte_size());
+        bytes.extend_from_slice(&self.position.to_bytes());
+        bytes.push(self.moves.len() as u8);
+        for data in &self.moves {
+            bytes.extend_from_slice(&data.mv.get_raw().to_le_bytes());
+            bytes.extend_from_slice(&data.visits.to_le_bytes());
+        }
+        bytes
+    }
+
+    //Reads one record from the start of the bytes, returns it with the number of bytes used
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.len() < Self::HEADER_SIZE {
+            return Err(invalid_data("policy record is too short"));
+        }
+
+        let move_count = bytes[ChessBoardRecord::SIZE] as usize;
+        let size = Self::HEADER_SIZE + move_count * 4;
+        if bytes.len() < size {
+            return Err(invalid_data("policy record moves are truncated"));
+        }
+
+        let moves = bytes[Self::HEADER_SIZE..size]
+            .chunks_exact(4)
+            .map(|chunk| PolicyMoveData {
+                mv: Move::from_raw(u16::from_le_bytes([chunk[0], chunk[1]])),
+                visits: u16::from_le_bytes([chunk[2], chunk[3]]),
+            })
+            .collect();
+        let result = Self {
+            position: ChessBoardRecord::from_bytes(&bytes[..ChessBoardRecord::SIZE])?,
+            moves,
+        };
+        Ok((result, size))
+    }
+
+    pub fn write_to<W: Write>(&self, inner: &mut W) -> io::Result<()> {
+        inner.write_all(&self.to_bytes())
+    }
+
+    pub fn read_from<Rd: Read>(inner: &mut Rd) -> io::Result<Self> {
+        let mut bytes = vec![0; Self::HEADER_SIZE];
+        inner.read_exact(&mut bytes)?;
+        let move_count = bytes[ChessBoardRecord::SIZE] as usize;
+        bytes.resize(Self::HEADER_SIZE + move_count * 4, 0);
+        inner.read_exact(&mut bytes[Self::HEADER_SIZE..])?;
+        Self::from_bytes(&bytes).map(|(record, _)| record)
+    }
+}
+
+impl From<&PolicyPacked> for PolicyRecord {
+    fn from(pack: &PolicyPacked) -> Self {
+        Self::from_policy_pack(pack)
+    }
+}
+
+impl PackedRecord for PolicyRecord {
+    const SIZE: usize = PolicyRecord::HEADER_SIZE;
+    const KIND: u8 = 5;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_bytes())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes(bytes).map(|(record, _)| record)
+    }
+
+    #[inline]
+    fn byte_size(&self) -> usize {
+        PolicyRecord::byte_size(self)
+    }
+
+    #[inline]
+    fn remaining_size(fixed_part: &[u8]) -> usize {
+        fixed_part[ChessBoardRecord::SIZE] as usize * 4
+    }
+}
+
+fn quantize_visits(visits: u64, max_visits: u64) -> u16 {
+    if max_visits <= u16::MAX as u64 {
+        return visits as u16;
+    }
+
+    let scaled = (visits as u128 * u16::MAX as u128 / max_visits as u128) as u16;
+    if visits > 0 {
+        scaled.max(1)
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor};
+
+    use super::PolicyRecord;
+    use crate::{
+        binpacks::{read_packed, write_packed, PackedReader},
+        ChessBoard, DataLoader, DataLoaderOptions, GameResult, FEN,
+    };
+
+    fn records() -> Vec<PolicyRecord> {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/8/8/8/8/8/6k1/4K3 b - - 12 80",
+        ];
+        fens.iter()
+            .enumerate()
+            .map(|(index, fen)| {
+                let board = ChessBoard::from_fen(&FEN::from_str(fen));
+                let mut record = PolicyRecord::from_board(&board, 25, GameResult::Draw);
+                let visits: Vec<_> = board
+                    .get_legal_moves()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(move_index, mv)| (mv, (move_index * index) as u64 * 40_000))
+                    .collect();
+                assert!(record.set_visit_counts(&visits));
+                record
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packed_files_round_trip_with_checksum() {
+        let records = records();
+        for checksum in [false, true] {
+            let bytes = write_packed(Vec::new(), &records, checksum).unwrap();
+            assert!(read_packed::<_, PolicyRecord>(bytes.as_slice()).unwrap() == records);
+
+            let mut reader: PackedReader<_, PolicyRecord> =
+                PackedReader::new(Cursor::new(&bytes)).unwrap();
+            assert!(reader.read().unwrap().unwrap() == records[0]);
+        }
+
+        let mut bytes = write_packed(Vec::new(), &records, true).unwrap();
+        let last = bytes.len() - 5;
+        bytes[last] ^= 1;
+        assert!(read_packed::<_, PolicyRecord>(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn data_loader_streams_policy_records() {
+        let path =
+            std::env::temp_dir().join(format!("spear-policy-records-{}.bin", std::process::id()));
+        let records = records();
+        write_packed(fs::File::create(&path).unwrap(), &records, true).unwrap();
+
+        let options = DataLoaderOptions {
+            batch_size: 2,
+            ..Default::default()
+        };
+        let mut loaded: Vec<PolicyRecord> = DataLoader::new(vec![path.clone()], options)
+            .unwrap()
+            .flat_map(Result::unwrap)
+            .collect();
+        fs::remove_file(path).unwrap();
+
+        loaded.sort_by_key(|record| record.move_count());
+        let mut expected = records;
+        expected.sort_by_key(|record| record.move_count());
+        assert!(loaded == expected);
+    }
+}
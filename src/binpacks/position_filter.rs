@@ -0,0 +1,328 @@
+use crate::{base_structures::Side, ChessBoard, Move};
+
+use super::{
+    BinpackEntry, BulletFormat, ChessBoardPacked, ChessBoardRecord, MarlinFormat, PolicyMoveData,
+    PolicyPacked, PolicyRecord,
+};
+
+//Predicates rejecting positions from training data. Predicates needing a best move or a score
+//never reject positions that come without one.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PositionFilter {
+    //Side to move is in check
+    InCheck,
+    //Best move is a capture or a promotion
+    TacticalBestMove,
+    //Side to move has a capture with a static exchange evaluation of at least the threshold
+    WinningCapture(i32),
+    InsufficientMaterial,
+    //Side to move score, in centipawns, is further than the limit from 0
+    ExtremeScore(i32),
+    //Rejects when any of the filters rejects
+    Any(Vec<PositionFilter>),
+    //Rejects when all of the filters reject
+    All(Vec<PositionFilter>),
+    Not(Box<PositionFilter>),
+}
+
+//Position of a record as the filters see it, the score is from the side to move's point of view
+#[derive(Clone, Copy)]
+pub struct FilterInput {
+    pub board: ChessBoard,
+    pub best_move: Option<Move>,
+    pub score: Option<i32>,
+}
+
+//Records the filters can be applied to
+pub trait FilterableRecord {
+    fn filter_input(&self) -> FilterInput;
+}
+
+impl PositionFilter {
+    //Quiet, non-check, decided-but-not-won positions, the usual selection for value training
+    pub fn quiet(max_score: i32) -> Self {
+        Self::Any(vec![
+            Self::InCheck,
+            Self::TacticalBestMove,
+            Self::WinningCapture(100),
+            Self::InsufficientMaterial,
+            Self::ExtremeScore(max_score),
+        ])
+    }
+
+    pub fn or(self, other: PositionFilter) -> Self {
+        match self {
+            Self::Any(mut filters) => {
+                filters.push(other);
+                Self::Any(filters)
+            }
+            filter => Self::Any(vec![filter, other]),
+        }
+    }
+
+    pub fn and(self, other: PositionFilter) -> Self {
+        match self {
+            Self::All(mut filters) => {
+                filters.push(other);
+                Self::All(filters)
+            }
+            filter => Self::All(vec![filter, other]),
+        }
+    }
+
+    pub fn rejects(&self, board: &ChessBoard, best_move: Option<Move>, score: Option<i32>) -> bool {
+        match self {
+            Self::InCheck => board.is_side_to_move_in_check(),
+            Self::TacticalBestMove => {
+                best_move.is_some_and(|mv| mv.is_capture() || mv.is_promotion())
+            }
+            Self::WinningCapture(threshold) => has_winning_capture(board, *threshold),
+            Self::InsufficientMaterial => board.is_insufficient_material(),
+            Self::ExtremeScore(limit) => score.is_some_and(|score| score.abs() > *limit),
+            Self::Any(filters) => filters
+                .iter()
+                .any(|filter| filter.rejects(board, best_move, score)),
+            Self::All(filters) => filters
+                .iter()
+                .all(|filter| filter.rejects(board, best_move, score)),
+            Self::Not(filter) => !filter.rejects(board, best_move, score),
+        }
+    }
+
+    #[inline]
+    pub fn accepts(&self, board: &ChessBoard, best_move: Option<Move>, score: Option<i32>) -> bool {
+        !self.rejects(board, best_move, score)
+    }
+
+    pub fn rejects_record<R: FilterableRecord>(&self, record: &R) -> bool {
+        let input = record.filter_input();
+        self.rejects(&input.board, input.best_move, input.score)
+    }
+
+    #[inline]
+    pub fn accepts_record<R: FilterableRecord>(&self, record: &R) -> bool {
+        !self.rejects_record(record)
+    }
+}
+
+fn has_winning_capture(board: &ChessBoard, threshold: i32) -> bool {
+    let mut result = false;
+    board.map_legal_moves(|mv| {
+        if !result && mv.is_capture() {
+            result = board.see_at_least(mv, threshold)
+        }
+    });
+    result
+}
+
+fn stm_score(white_score: i16, side_to_move: Side) -> i32 {
+    if side_to_move == Side::WHITE {
+        white_score as i32
+    } else {
+        -(white_score as i32)
+    }
+}
+
+fn most_visited(moves: &[PolicyMoveData]) -> Option<Move> {
+    moves
+        .iter()
+        .max_by_key(|data| data.visits)
+        .map(|data| data.mv)
+}
+
+//The score is a 0 to 1 fraction without a fixed centipawn scale, so ExtremeScore ignores it
+impl FilterableRecord for ChessBoardPacked {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: ChessBoard::from_board_pack(self),
+            best_move: None,
+            score: None,
+        }
+    }
+}
+
+impl FilterableRecord for ChessBoardRecord {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: self.to_board(),
+            best_move: None,
+            score: Some(stm_score(
+                self.get_white_perspective_eval(),
+                self.get_side_to_move(),
+            )),
+        }
+    }
+}
+
+impl FilterableRecord for MarlinFormat {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: self.to_board(),
+            best_move: None,
+            score: Some(stm_score(
+                self.get_white_perspective_eval(),
+                self.get_side_to_move(),
+            )),
+        }
+    }
+}
+
+//Bullet boards are stored from the side to move's point of view, which the filters do not mind
+impl FilterableRecord for BulletFormat {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: self.to_board(Side::WHITE),
+            best_move: None,
+            score: Some(self.get_score() as i32),
+        }
+    }
+}
+
+impl FilterableRecord for PolicyPacked {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: ChessBoard::from_policy_pack(self),
+            best_move: most_visited(&self.moves()[..self.move_count() as usize]),
+            score: None,
+        }
+    }
+}
+
+impl FilterableRecord for PolicyRecord {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: self.to_board(),
+            best_move: most_visited(self.moves()),
+            score: Some(stm_score(
+                self.position.get_white_perspective_eval(),
+                self.position.get_side_to_move(),
+            )),
+        }
+    }
+}
+
+impl FilterableRecord for BinpackEntry {
+    fn filter_input(&self) -> FilterInput {
+        FilterInput {
+            board: self.board,
+            best_move: (self.mv != Move::NULL).then_some(self.mv),
+            score: Some(self.score as i32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PositionFilter;
+    use crate::{ChessBoard, ChessBoardPacked, PolicyPacked, FEN};
+
+    const QUIET: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const IN_CHECK: &str = "4k3/8/8/8/8/8/2n5/R3K3 w - - 0 1";
+    const HANGING_PAWN: &str = "4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1";
+    const DEFENDED_PAWN: &str = "4k3/8/4p3/3p4/8/8/8/3RK3 w - - 0 1";
+    const BARE_KINGS: &str = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::from_fen(&FEN::from_str(fen))
+    }
+
+    #[test]
+    fn single_predicates() {
+        assert!(PositionFilter::InCheck.rejects(&board(IN_CHECK), None, None));
+        assert!(PositionFilter::InCheck.accepts(&board(QUIET), None, None));
+
+        assert!(PositionFilter::WinningCapture(100).rejects(&board(HANGING_PAWN), None, None));
+        assert!(PositionFilter::WinningCapture(101).accepts(&board(HANGING_PAWN), None, None));
+        assert!(PositionFilter::WinningCapture(0).accepts(&board(DEFENDED_PAWN), None, None));
+
+        assert!(PositionFilter::InsufficientMaterial.rejects(&board(BARE_KINGS), None, None));
+        assert!(PositionFilter::InsufficientMaterial.accepts(&board(HANGING_PAWN), None, None));
+
+        let quiet = board(QUIET);
+        assert!(PositionFilter::ExtremeScore(300).rejects(&quiet, None, Some(301)));
+        assert!(PositionFilter::ExtremeScore(300).rejects(&quiet, None, Some(-301)));
+        assert!(PositionFilter::ExtremeScore(300).accepts(&quiet, None, Some(300)));
+
+        let hanging_pawn = board(HANGING_PAWN);
+        let capture = hanging_pawn.parse_uci_move("d1d5");
+        let quiet_move = hanging_pawn.parse_uci_move("d1d4");
+        assert!(PositionFilter::TacticalBestMove.rejects(&hanging_pawn, capture, None));
+        assert!(PositionFilter::TacticalBestMove.accepts(&hanging_pawn, quiet_move, None));
+        let promotion_board = board("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let promotion = promotion_board.parse_uci_move("b7b8q");
+        assert!(PositionFilter::TacticalBestMove.rejects(&promotion_board, promotion, None));
+    }
+
+    #[test]
+    fn quiet_filter() {
+        let filter = PositionFilter::quiet(300);
+        let quiet = board(QUIET);
+        let e2e4 = quiet.parse_uci_move("e2e4");
+
+        assert!(filter.accepts(&quiet, e2e4, Some(20)));
+        assert!(filter.accepts(&quiet, None, None));
+        assert!(filter.rejects(&quiet, e2e4, Some(-400)));
+        assert!(filter.rejects(&board(IN_CHECK), None, Some(0)));
+        assert!(filter.rejects(&board(HANGING_PAWN), None, Some(0)));
+        assert!(filter.accepts(&board(DEFENDED_PAWN), None, Some(0)));
+        assert!(filter.rejects(&board(BARE_KINGS), None, Some(0)));
+
+        let hanging_pawn = board(HANGING_PAWN);
+        let capture = hanging_pawn.parse_uci_move("d1d5");
+        assert!(filter.rejects(&board(DEFENDED_PAWN), capture, Some(0)));
+    }
+
+    #[test]
+    fn combinators() {
+        let in_check = board(IN_CHECK);
+        let quiet = board(QUIET);
+        let extreme = PositionFilter::ExtremeScore(100);
+
+        let any = PositionFilter::InCheck.or(extreme.clone());
+        assert!(any == PositionFilter::Any(vec![PositionFilter::InCheck, extreme.clone()]));
+        assert!(any.rejects(&in_check, None, Some(0)));
+        assert!(any.rejects(&quiet, None, Some(200)));
+        assert!(any.accepts(&quiet, None, Some(0)));
+
+        let all = PositionFilter::InCheck.and(extreme.clone());
+        assert!(all == PositionFilter::All(vec![PositionFilter::InCheck, extreme.clone()]));
+        assert!(all.accepts(&in_check, None, Some(0)));
+        assert!(all.accepts(&quiet, None, Some(200)));
+        assert!(all.rejects(&in_check, None, Some(200)));
+
+        let not = PositionFilter::Not(Box::new(PositionFilter::InCheck));
+        assert!(not.rejects(&quiet, None, None));
+        assert!(not.accepts(&in_check, None, None));
+
+        let chained = any.or(PositionFilter::InsufficientMaterial);
+        assert!(matches!(&chained, PositionFilter::Any(filters) if filters.len() == 3));
+        assert!(chained.rejects(&board(BARE_KINGS), None, None));
+
+        assert!(PositionFilter::Any(Vec::new()).accepts(&quiet, None, None));
+        assert!(PositionFilter::All(Vec::new()).rejects(&quiet, None, None));
+    }
+
+    #[test]
+    fn missing_best_move_and_score_never_reject() {
+        let hanging_pawn = board(HANGING_PAWN);
+        let score_filter = PositionFilter::ExtremeScore(0);
+        let move_filter = PositionFilter::TacticalBestMove;
+
+        assert!(score_filter.accepts(&hanging_pawn, None, None));
+        assert!(move_filter.accepts(&hanging_pawn, None, None));
+
+        //Packed boards have no centipawn score, policy packs without moves have no best move
+        let packed = ChessBoardPacked::from_board(&hanging_pawn, 0.99);
+        assert!(score_filter.accepts_record(&packed));
+        assert!(move_filter.accepts_record(&packed));
+        let policy = PolicyPacked::from_board(&hanging_pawn);
+        assert!(move_filter.accepts_record(&policy));
+
+        let mut policy = PolicyPacked::from_board(&hanging_pawn);
+        let capture = hanging_pawn.parse_uci_move("d1d5").unwrap();
+        let quiet_move = hanging_pawn.parse_uci_move("d1d4").unwrap();
+        assert!(policy.push_move(quiet_move, 10));
+        assert!(policy.push_move(capture, 90));
+        assert!(move_filter.rejects_record(&policy));
+    }
+}
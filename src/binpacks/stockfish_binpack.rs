@@ -0,0 +1,989 @@
+use std::io::{self, Read, Write};
+
+use crate::{Attacks, Bitboard, CastleRights, ChessBoard, Move, MoveFlag, Piece, Side, Square};
+
+use super::{
+    marlin_format::castle_right_for_rook,
+    packed_io::{invalid_data, invalid_input},
+    GameResult,
+};
+
+//Stockfish .binpack files as produced by the nnue-pytorch tools. A file is a sequence of chunks,
+//"BINP" | chunk size u32 LE | chains. A chain is a 32 byte stem entry, the number of plies that
+//follow it (u16 BE) and a movetext bitstream holding the move and score of every following
+//position, so consecutive positions of a game take a few bits each instead of a full entry.
+//Stem, all values big endian:
+//occupancy u64 | 32 piece nibbles in occupancy order | move u16 | score u16 |
+//ply u14 + result u2 | rule50 u16
+//A piece nibble is piece type * 2 + color, 12 for a pawn that can be captured en passant,
+//13/14 for a white/black rook that can still castle and 15 for the black king with black to move.
+//A move is type u2 (normal, promotion, castle, en passant) | from u6 | to u6 | promotion u2,
+//castling is stored as the king capturing its own rook.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct BinpackEntry {
+    pub board: ChessBoard,
+    pub mv: Move,
+    //centipawns from the side to move's point of view
+    pub score: i16,
+    pub ply: u16,
+    //1 win, 0 draw, -1 loss for the side to move
+    pub result: i8,
+}
+
+pub struct StockfishBinpackReader<R: Read> {
+    inner: R,
+    chunk: Vec<u8>,
+    offset: usize,
+    //last entry of the chain being decoded and the movetext read position in bits
+    entry: BinpackEntry,
+    remaining_plies: u16,
+    bit_position: usize,
+    last_score: i16,
+}
+
+pub struct StockfishBinpackWriter<W: Write> {
+    inner: W,
+    chunk: Vec<u8>,
+    //last written entry, None when no chain is open
+    last: Option<BinpackEntry>,
+    movetext: BitWriter,
+    plies: u16,
+    last_score: i16,
+}
+
+const CHUNK_MAGIC: [u8; 4] = *b"BINP";
+const CHUNK_HEADER_SIZE: usize = 8;
+const SUGGESTED_CHUNK_SIZE: usize = 1024 * 1024;
+const MAX_CHUNK_SIZE: usize = 100 * 1024 * 1024;
+const STEM_SIZE: usize = 32;
+const SCORE_BLOCK_SIZE: usize = 4;
+
+const NORMAL_MOVE: u16 = 0;
+const PROMOTION_MOVE: u16 = 1;
+const CASTLE_MOVE: u16 = 2;
+const EN_PASSANT_MOVE: u16 = 3;
+
+const EN_PASSANT_PAWN: u8 = 12;
+const CASTLE_ROOK: u8 = 13;
+const BLACK_KING_TO_MOVE: u8 = 15;
+
+impl BinpackEntry {
+    //Unknown results are stored as draws, the format has no value for them
+    pub fn new(board: ChessBoard, mv: Move, score: i16, ply: u16, result: GameResult) -> Self {
+        let white_result = match result {
+            GameResult::WhiteWin => 1,
+            GameResult::BlackWin => -1,
+            GameResult::Draw | GameResult::Unknown => 0,
+        };
+        Self {
+            board,
+            mv,
+            score,
+            ply,
+            result: if board.side_to_move() == Side::WHITE {
+                white_result
+            } else {
+                -white_result
+            },
+        }
+    }
+
+    pub fn get_result(&self) -> GameResult {
+        let white_result = if self.board.side_to_move() == Side::WHITE {
+            self.result
+        } else {
+            -self.result
+        };
+        match white_result {
+            1 => GameResult::WhiteWin,
+            -1 => GameResult::BlackWin,
+            _ => GameResult::Draw,
+        }
+    }
+
+    #[inline]
+    pub fn get_white_perspective_score(&self) -> i16 {
+        if self.board.side_to_move() == Side::WHITE {
+            self.score
+        } else {
+            self.score.saturating_neg()
+        }
+    }
+}
+
+impl<R: Read> StockfishBinpackReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            chunk: Vec::new(),
+            offset: 0,
+            entry: BinpackEntry::default(),
+            remaining_plies: 0,
+            bit_position: 0,
+            last_score: 0,
+        }
+    }
+
+    //Reads the next entry, None at the end of the input
+    pub fn read(&mut self) -> io::Result<Option<BinpackEntry>> {
+        if self.remaining_plies > 0 {
+            return self.read_chain_entry().map(Some);
+        }
+
+        if self.offset == self.chunk.len() && !self.read_chunk()? {
+            return Ok(None);
+        }
+
+        self.read_chain_stem().map(Some)
+    }
+
+    //Loads the next non empty chunk, false at the end of the input
+    fn read_chunk(&mut self) -> io::Result<bool> {
+        loop {
+            let mut header = [0; CHUNK_HEADER_SIZE];
+            match read_full(&mut self.inner, &mut header)? {
+                0 => return Ok(false),
+                CHUNK_HEADER_SIZE => (),
+                _ => return Err(invalid_data("truncated binpack chunk header")),
+            }
+
+            if header[0..4] != CHUNK_MAGIC {
+                return Err(invalid_data("not a binpack chunk"));
+            }
+            let size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+            if size > MAX_CHUNK_SIZE {
+                return Err(invalid_data(format!(
+                    "binpack chunk of {size} bytes is too large"
+                )));
+            }
+
+            self.chunk.resize(size, 0);
+            self.inner.read_exact(&mut self.chunk)?;
+            self.offset = 0;
+            if size > 0 {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn read_chain_stem(&mut self) -> io::Result<BinpackEntry> {
+        let Some(bytes) = self.chunk.get(self.offset..self.offset + STEM_SIZE + 2) else {
+            return Err(invalid_data("truncated binpack chain"));
+        };
+
+        let entry = read_stem(&bytes[..STEM_SIZE])?;
+        self.remaining_plies = u16::from_be_bytes([bytes[STEM_SIZE], bytes[STEM_SIZE + 1]]);
+        if self.remaining_plies > 0 && entry.mv == Move::NULL {
+            return Err(invalid_data("binpack chain continues after a null move"));
+        }
+
+        self.offset += STEM_SIZE + 2;
+        self.bit_position = 0;
+        self.last_score = entry.score.wrapping_neg();
+        self.entry = entry;
+        Ok(entry)
+    }
+
+    fn read_chain_entry(&mut self) -> io::Result<BinpackEntry> {
+        let mut entry = self.entry;
+        entry.board.apply_move(entry.mv);
+        clear_unused_en_passant(&mut entry.board);
+
+        let mut bits = BitReader {
+            bytes: &self.chunk[self.offset..],
+            position: self.bit_position,
+        };
+        entry.mv = read_movetext_move(&entry.board, &mut bits)?;
+        entry.score = self
+            .last_score
+            .wrapping_add(unsigned_to_signed(bits.read_vle(SCORE_BLOCK_SIZE)?));
+        entry.ply = entry.ply.wrapping_add(1);
+        entry.result = -entry.result;
+
+        self.bit_position = bits.position;
+        self.last_score = entry.score.wrapping_neg();
+        self.remaining_plies -= 1;
+        if self.remaining_plies == 0 {
+            self.offset += self.bit_position.div_ceil(8);
+        }
+        self.entry = entry;
+        Ok(entry)
+    }
+}
+
+impl<R: Read> Iterator for StockfishBinpackReader<R> {
+    type Item = io::Result<BinpackEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+impl<W: Write> StockfishBinpackWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            chunk: Vec::new(),
+            last: None,
+            movetext: BitWriter::default(),
+            plies: 0,
+            last_score: 0,
+        }
+    }
+
+    //Entries are chained into the movetext of the previous one when they are the position
+    //reached by its move, one ply later and with the opposite result. Moves have to be legal.
+    pub fn write(&mut self, entry: &BinpackEntry) -> io::Result<()> {
+        if !(-1..=1).contains(&entry.result) {
+            return Err(invalid_input(format!("invalid result {}", entry.result)));
+        }
+
+        if let Some(board) = self.continuation_board(entry) {
+            write_movetext_move(&board, entry.mv, &mut self.movetext);
+            self.movetext.write_vle(
+                signed_to_unsigned(entry.score.wrapping_sub(self.last_score)),
+                SCORE_BLOCK_SIZE,
+            );
+            self.plies += 1;
+        } else {
+            self.end_chain();
+            if self.chunk.len() >= SUGGESTED_CHUNK_SIZE {
+                self.write_chunk()?;
+            }
+
+            let mut stem = [0; STEM_SIZE];
+            write_stem(entry, &mut stem);
+            self.chunk.extend_from_slice(&stem);
+        }
+
+        self.last_score = entry.score.wrapping_neg();
+        self.last = Some(*entry);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end_chain();
+        self.write_chunk()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    //Board the reader rebuilds for the entry when it continues the open chain. Null moves
+    //have no movetext encoding, so they always start a new chain.
+    fn continuation_board(&self, entry: &BinpackEntry) -> Option<ChessBoard> {
+        let last = self.last?;
+        if last.mv == Move::NULL
+            || entry.mv == Move::NULL
+            || self.plies == u16::MAX
+            || last.ply.wrapping_add(1) != entry.ply
+            || last.result != -entry.result
+        {
+            return None;
+        }
+
+        let mut board = last.board;
+        board.apply_move(last.mv);
+        clear_unused_en_passant(&mut board);
+        let mut expected = entry.board;
+        clear_unused_en_passant(&mut expected);
+        (board.get_key() == expected.get_key()).then_some(board)
+    }
+
+    fn end_chain(&mut self) {
+        if self.last.take().is_none() {
+            return;
+        }
+
+        self.chunk.extend_from_slice(&self.plies.to_be_bytes());
+        self.chunk.append(&mut self.movetext.bytes);
+        self.movetext = BitWriter::default();
+        self.plies = 0;
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+
+        self.inner.write_all(&CHUNK_MAGIC)?;
+        self.inner
+            .write_all(&(self.chunk.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&self.chunk)?;
+        self.chunk.clear();
+        Ok(())
+    }
+}
+
+pub fn write_stockfish_binpack<W: Write>(inner: W, entries: &[BinpackEntry]) -> io::Result<W> {
+    let mut writer = StockfishBinpackWriter::new(inner);
+    for entry in entries {
+        writer.write(entry)?;
+    }
+    writer.finish()
+}
+
+pub fn read_stockfish_binpack<R: Read>(inner: R) -> io::Result<Vec<BinpackEntry>> {
+    StockfishBinpackReader::new(inner).collect()
+}
+
+fn write_stem(entry: &BinpackEntry, bytes: &mut [u8]) {
+    let board = &entry.board;
+    let en_passant = legal_en_passant_square(board);
+    let occupancy = board.get_occupancy();
+
+    let mut pieces = [0u8; 16];
+    let mut index = 0;
+    occupancy.map(|square| {
+        pieces[index / 2] |= piece_nibble(board, square, en_passant) << (4 * (index % 2));
+        index += 1;
+    });
+
+    let ply_result = (entry.ply & 0x3FFF) | (signed_to_unsigned(entry.result as i16) << 14);
+    bytes[0..8].copy_from_slice(&occupancy.get_raw().to_be_bytes());
+    bytes[8..24].copy_from_slice(&pieces);
+    bytes[24..26].copy_from_slice(&encode_move(entry.mv).to_be_bytes());
+    bytes[26..28].copy_from_slice(&signed_to_unsigned(entry.score).to_be_bytes());
+    bytes[28..30].copy_from_slice(&ply_result.to_be_bytes());
+    bytes[30..32].copy_from_slice(&(board.half_move_counter() as u16).to_be_bytes());
+}
+
+fn read_stem(bytes: &[u8]) -> io::Result<BinpackEntry> {
+    let occupancy = Bitboard::from_raw(u64::from_be_bytes(bytes[0..8].try_into().unwrap()));
+    if occupancy.pop_count() > 32 {
+        return Err(invalid_data("more than 32 pieces"));
+    }
+
+    let mut board = ChessBoard::default();
+    let mut castle_rights = CastleRights::NULL;
+    let mut en_passant = Square::NULL;
+    let mut side_to_move = Side::WHITE;
+    let mut valid = true;
+    let mut index = 0;
+    occupancy.map(|square| {
+        let nibble = (bytes[8 + index / 2] >> (4 * (index % 2))) & 0xF;
+        index += 1;
+
+        let (piece, side) = match nibble {
+            EN_PASSANT_PAWN => match square.get_rank() {
+                3 => {
+                    en_passant = square.shift_right(8);
+                    (Piece::PAWN, Side::WHITE)
+                }
+                4 => {
+                    en_passant = square.shift_left(8);
+                    (Piece::PAWN, Side::BLACK)
+                }
+                _ => {
+                    valid = false;
+                    return;
+                }
+            },
+            13 | 14 => {
+                let side = Side::from_raw(nibble - CASTLE_ROOK);
+                match castle_right_for_rook(square, side) {
+                    Some(right) => castle_rights.set_right(right),
+                    None => valid = false,
+                }
+                (Piece::ROOK, side)
+            }
+            BLACK_KING_TO_MOVE => {
+                side_to_move = Side::BLACK;
+                (Piece::KING, Side::BLACK)
+            }
+            _ => (Piece::from_raw(nibble >> 1), Side::from_raw(nibble & 1)),
+        };
+
+        if side == Side::WHITE {
+            board.set_piece_on_square::<true>(square, piece);
+        } else {
+            board.set_piece_on_square::<false>(square, piece);
+        }
+    });
+    if !valid {
+        return Err(invalid_data("invalid piece nibble"));
+    }
+
+    let ply_result = u16::from_be_bytes([bytes[28], bytes[29]]);
+    let ply = ply_result & 0x3FFF;
+    let result = unsigned_to_signed(ply_result >> 14);
+    if !(-1..=1).contains(&result) {
+        return Err(invalid_data(format!("invalid result {result}")));
+    }
+    let rule50 = u16::from_be_bytes([bytes[30], bytes[31]]);
+
+    board.set_side_to_move(side_to_move);
+    board.set_castle_rights(castle_rights);
+    board.set_en_passant_square(en_passant);
+    board.set_move_counters(rule50.min(u8::MAX as u16) as u8, ply / 2 + 1);
+    board.refresh_phase();
+    board
+        .check_invariants()
+        .map_err(|error| invalid_data(error.to_string()))?;
+
+    Ok(BinpackEntry {
+        board,
+        mv: decode_move(&board, u16::from_be_bytes([bytes[24], bytes[25]]))?,
+        score: unsigned_to_signed(u16::from_be_bytes([bytes[26], bytes[27]])),
+        ply,
+        result: result as i8,
+    })
+}
+
+fn piece_nibble(board: &ChessBoard, square: Square, en_passant: Square) -> u8 {
+    let piece = board.get_piece_on_square(square);
+    let side = board.get_piece_color_on_square(square);
+
+    //The pawn that can be captured stands right in front of the en passant square
+    if piece == Piece::PAWN && en_passant != Square::NULL {
+        let pawn_square = if side == Side::WHITE {
+            en_passant.shift_left(8)
+        } else {
+            en_passant.shift_right(8)
+        };
+        if square == pawn_square {
+            return EN_PASSANT_PAWN;
+        }
+    }
+    if piece == Piece::ROOK
+        && castle_right_for_rook(square, side)
+            .is_some_and(|right| board.castle_rights().has_right(right))
+    {
+        return CASTLE_ROOK + side.get_raw();
+    }
+    if piece == Piece::KING && side == Side::BLACK && board.side_to_move() == Side::BLACK {
+        return BLACK_KING_TO_MOVE;
+    }
+
+    piece.get_raw() * 2 + side.get_raw()
+}
+
+fn encode_move(mv: Move) -> u16 {
+    if mv == Move::NULL {
+        return 0;
+    }
+
+    let from = mv.get_from_square().get_raw() as u16;
+    let to = mv.get_to_square().get_raw() as u16;
+    let (move_type, to, promotion) = if mv.is_castle() {
+        (CASTLE_MOVE, castle_rook_square(mv).get_raw() as u16, 0)
+    } else if mv.is_en_passant() {
+        (EN_PASSANT_MOVE, to, 0)
+    } else if mv.is_promotion() {
+        (
+            PROMOTION_MOVE,
+            to,
+            (mv.get_promotion_piece().get_raw() - Piece::KNIGHT.get_raw()) as u16,
+        )
+    } else {
+        (NORMAL_MOVE, to, 0)
+    };
+
+    move_type << 14 | from << 8 | to << 2 | promotion
+}
+
+fn decode_move(board: &ChessBoard, value: u16) -> io::Result<Move> {
+    if value == 0 {
+        return Ok(Move::NULL);
+    }
+
+    let move_type = value >> 14;
+    let from = Square::from_raw(((value >> 8) & 63) as u8);
+    let to = Square::from_raw(((value >> 2) & 63) as u8);
+    let promotion = Piece::from_raw((value & 3) as u8 + Piece::KNIGHT.get_raw());
+    find_legal_move(board, |mv| {
+        mv.get_from_square() == from
+            && match move_type {
+                CASTLE_MOVE => mv.is_castle() && castle_rook_square(mv) == to,
+                PROMOTION_MOVE => {
+                    mv.is_promotion()
+                        && mv.get_to_square() == to
+                        && mv.get_promotion_piece() == promotion
+                }
+                _ => !mv.is_castle() && !mv.is_promotion() && mv.get_to_square() == to,
+            }
+    })
+}
+
+fn write_movetext_move(board: &ChessBoard, mv: Move, bits: &mut BitWriter) {
+    let (ours, theirs) = side_occupancies(board);
+    let from = mv.get_from_square();
+    let to = mv.get_to_square();
+    bits.write(
+        (ours & squares_below(from)).pop_count() as u16,
+        used_bits(ours.pop_count()),
+    );
+
+    let (index, count) = match board.get_piece_on_square(from) {
+        Piece::PAWN => {
+            let destinations = pawn_destinations(board, from, theirs, ours | theirs);
+            let below = (destinations & squares_below(to)).pop_count();
+            if mv.is_promotion() {
+                let promotion =
+                    (mv.get_promotion_piece().get_raw() - Piece::KNIGHT.get_raw()) as u32;
+                (below * 4 + promotion, destinations.pop_count() * 4)
+            } else {
+                (below, destinations.pop_count())
+            }
+        }
+        Piece::KING => {
+            let attacks = Attacks::get_king_attacks_for_square(from) & !ours;
+            let (queen_side, king_side) = castle_rights_for_side(board);
+            let count = attacks.pop_count() + queen_side as u32 + king_side as u32;
+            if mv.is_castle() {
+                //Castles come after the regular king moves, queen side first
+                let index = attacks.pop_count() + u32::from(queen_side);
+                if mv.get_flag() == MoveFlag::KING_SIDE_CASTLE {
+                    (index, count)
+                } else {
+                    (index - 1, count)
+                }
+            } else {
+                ((attacks & squares_below(to)).pop_count(), count)
+            }
+        }
+        piece => {
+            let attacks = piece_attacks(piece, from, ours | theirs) & !ours;
+            (
+                (attacks & squares_below(to)).pop_count(),
+                attacks.pop_count(),
+            )
+        }
+    };
+    bits.write(index as u16, used_bits(count));
+}
+
+fn read_movetext_move(board: &ChessBoard, bits: &mut BitReader) -> io::Result<Move> {
+    let (ours, theirs) = side_occupancies(board);
+    let from = nth_square(ours, bits.read(used_bits(ours.pop_count()))?)?;
+
+    match board.get_piece_on_square(from) {
+        Piece::PAWN => {
+            let destinations = pawn_destinations(board, from, theirs, ours | theirs);
+            let promotion_rank = if board.side_to_move() == Side::WHITE {
+                6
+            } else {
+                1
+            };
+            if from.get_rank() == promotion_rank {
+                let index = bits.read(used_bits(destinations.pop_count() * 4))?;
+                let to = nth_square(destinations, index / 4)?;
+                let promotion = Piece::from_raw((index % 4) as u8 + Piece::KNIGHT.get_raw());
+                find_legal_move(board, |mv| {
+                    mv.get_from_square() == from
+                        && mv.get_to_square() == to
+                        && mv.is_promotion()
+                        && mv.get_promotion_piece() == promotion
+                })
+            } else {
+                let to = nth_square(
+                    destinations,
+                    bits.read(used_bits(destinations.pop_count()))?,
+                )?;
+                find_legal_move(board, |mv| {
+                    mv.get_from_square() == from && mv.get_to_square() == to
+                })
+            }
+        }
+        Piece::KING => {
+            let attacks = Attacks::get_king_attacks_for_square(from) & !ours;
+            let (queen_side, king_side) = castle_rights_for_side(board);
+            let count = attacks.pop_count() + queen_side as u32 + king_side as u32;
+            let index = bits.read(used_bits(count))? as u32;
+            if index >= attacks.pop_count() {
+                let flag = if index == attacks.pop_count() && queen_side {
+                    MoveFlag::QUEEN_SIDE_CASTLE
+                } else {
+                    MoveFlag::KING_SIDE_CASTLE
+                };
+                find_legal_move(board, |mv| mv.get_flag() == flag)
+            } else {
+                let to = nth_square(attacks, index as u16)?;
+                find_legal_move(board, |mv| {
+                    mv.get_from_square() == from && mv.get_to_square() == to && !mv.is_castle()
+                })
+            }
+        }
+        piece => {
+            let attacks = piece_attacks(piece, from, ours | theirs) & !ours;
+            let to = nth_square(attacks, bits.read(used_bits(attacks.pop_count()))?)?;
+            find_legal_move(board, |mv| {
+                mv.get_from_square() == from && mv.get_to_square() == to
+            })
+        }
+    }
+}
+
+fn side_occupancies(board: &ChessBoard) -> (Bitboard, Bitboard) {
+    let white = board.get_occupancy_for_side::<true>();
+    let black = board.get_occupancy_for_side::<false>();
+    if board.side_to_move() == Side::WHITE {
+        (white, black)
+    } else {
+        (black, white)
+    }
+}
+
+//Pushes and captures of the pawn, ignoring pins and promotions
+fn pawn_destinations(
+    board: &ChessBoard,
+    from: Square,
+    theirs: Bitboard,
+    occupancy: Bitboard,
+) -> Bitboard {
+    let white = board.side_to_move() == Side::WHITE;
+    let mut targets = theirs;
+    let en_passant = legal_en_passant_square(board);
+    if en_passant != Square::NULL {
+        targets.set_bit(en_passant);
+    }
+
+    let mut destinations = targets
+        & if white {
+            Attacks::get_pawn_attacks_for_square::<true>(from)
+        } else {
+            Attacks::get_pawn_attacks_for_square::<false>(from)
+        };
+
+    let forward = |square: Square| {
+        if white {
+            square.shift_left(8)
+        } else {
+            square.shift_right(8)
+        }
+    };
+    let single_push = forward(from);
+    if !occupancy.get_bit(single_push) {
+        destinations.set_bit(single_push);
+
+        let start_rank = if white { 1 } else { 6 };
+        let double_push = forward(single_push);
+        if from.get_rank() == start_rank && !occupancy.get_bit(double_push) {
+            destinations.set_bit(double_push);
+        }
+    }
+    destinations
+}
+
+fn piece_attacks(piece: Piece, square: Square, occupancy: Bitboard) -> Bitboard {
+    match piece {
+        Piece::KNIGHT => Attacks::get_knight_attacks_for_square(square),
+        Piece::BISHOP => Attacks::get_bishop_attacks_for_square(square, occupancy),
+        Piece::ROOK => Attacks::get_rook_attacks_for_square(square, occupancy),
+        _ => {
+            Attacks::get_bishop_attacks_for_square(square, occupancy)
+                | Attacks::get_rook_attacks_for_square(square, occupancy)
+        }
+    }
+}
+
+//(queen side, king side) castle rights of the side to move
+fn castle_rights_for_side(board: &ChessBoard) -> (bool, bool) {
+    let rights = board.castle_rights();
+    if board.side_to_move() == Side::WHITE {
+        (
+            rights.has_right(CastleRights::WHITE_QUEEN),
+            rights.has_right(CastleRights::WHITE_KING),
+        )
+    } else {
+        (
+            rights.has_right(CastleRights::BLACK_QUEEN),
+            rights.has_right(CastleRights::BLACK_KING),
+        )
+    }
+}
+
+fn castle_rook_square(mv: Move) -> Square {
+    if mv.get_flag() == MoveFlag::KING_SIDE_CASTLE {
+        mv.get_from_square().shift_left(3)
+    } else {
+        mv.get_from_square().shift_right(4)
+    }
+}
+
+//Stockfish only keeps the en passant square when the capture is legal
+fn legal_en_passant_square(board: &ChessBoard) -> Square {
+    if board.en_passant_square() == Square::NULL {
+        return Square::NULL;
+    }
+
+    let mut legal = false;
+    board.map_legal_moves(|mv| legal |= mv.is_en_passant());
+    if legal {
+        board.en_passant_square()
+    } else {
+        Square::NULL
+    }
+}
+
+fn clear_unused_en_passant(board: &mut ChessBoard) {
+    let en_passant = legal_en_passant_square(board);
+    board.set_en_passant_square(en_passant);
+}
+
+fn find_legal_move<F: Fn(Move) -> bool>(board: &ChessBoard, predicate: F) -> io::Result<Move> {
+    let mut result = None;
+    board.map_legal_moves(|mv| {
+        if result.is_none() && predicate(mv) {
+            result = Some(mv)
+        }
+    });
+    result.ok_or_else(|| invalid_data(format!("illegal move in {}", board.get_fen())))
+}
+
+#[inline]
+fn squares_below(square: Square) -> Bitboard {
+    Bitboard::from_raw((1u64 << square.get_raw()) - 1)
+}
+
+fn nth_square(mut bitboard: Bitboard, index: u16) -> io::Result<Square> {
+    if index as u32 >= bitboard.pop_count() {
+        return Err(invalid_data("movetext index out of range"));
+    }
+
+    for _ in 0..index {
+        bitboard.pop_ls1b_square();
+    }
+    Ok(bitboard.ls1b_square())
+}
+
+//Bits needed to store an index in 0..count
+#[inline]
+fn used_bits(count: u32) -> usize {
+    if count <= 1 {
+        0
+    } else {
+        (u32::BITS - (count - 1).leading_zeros()) as usize
+    }
+}
+
+//Zigzag style mapping keeping small magnitudes small: 0, -1, 1, -2, 2... map to 0, 1, 2, 3, 4...
+#[inline]
+fn signed_to_unsigned(value: i16) -> u16 {
+    let mut value = value as u16;
+    if value & 0x8000 > 0 {
+        value ^= 0x7FFF;
+    }
+    value.rotate_left(1)
+}
+
+#[inline]
+fn unsigned_to_signed(value: u16) -> i16 {
+    let mut value = value.rotate_right(1);
+    if value & 0x8000 > 0 {
+        value ^= 0x7FFF;
+    }
+    value as i16
+}
+
+//Reads until the buffer is full or the input ends, returns the number of bytes read
+fn read_full<R: Read>(inner: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match inner.read(&mut buffer[read..]) {
+            Ok(0) => break,
+            Ok(count) => read += count,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(read)
+}
+
+//Bitstreams are filled from the most significant bit of each byte
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    length: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, count: usize) -> io::Result<u16> {
+        let mut value = 0;
+        for _ in 0..count {
+            let Some(byte) = self.bytes.get(self.position / 8) else {
+                return Err(invalid_data("truncated binpack movetext"));
+            };
+            value = (value << 1) | ((byte >> (7 - self.position % 8)) & 1) as u16;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    //Blocks of `block_size` value bits, each preceded by a continuation bit
+    fn read_vle(&mut self, block_size: usize) -> io::Result<u16> {
+        let mask = (1 << block_size) - 1;
+        let mut value = 0;
+        let mut offset = 0;
+        loop {
+            let block = self.read(block_size + 1)?;
+            value |= (block & mask) << offset;
+            if block >> block_size == 0 {
+                return Ok(value);
+            }
+
+            offset += block_size;
+            if offset >= u16::BITS as usize {
+                return Err(invalid_data("binpack score is too long"));
+            }
+        }
+    }
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u16, count: usize) {
+        for bit in (0..count).rev() {
+            if self.length.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> bit) & 1 > 0 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.length % 8);
+            }
+            self.length += 1;
+        }
+    }
+
+    fn write_vle(&mut self, mut value: u16, block_size: usize) {
+        let mask = (1 << block_size) - 1;
+        loop {
+            let more = value > mask;
+            self.write(
+                (value & mask) | (u16::from(more) << block_size),
+                block_size + 1,
+            );
+            value >>= block_size;
+            if !more {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clear_unused_en_passant, read_stockfish_binpack, write_stockfish_binpack, BinpackEntry,
+    };
+    use crate::{ChessBoard, GameResult, FEN};
+
+    //Games covering captures, en passant, castling on both wings and promotions, including
+    //an underpromotion capture. Every game is written as a single chain.
+    const GAMES: [(&str, &str); 3] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e2e4 d7d5 e4d5 c7c5 d5c6 b8c6 g1f3 e7e5 f1b5 f8d6 e1g1 g8e7 d2d4 e8g8",
+        ),
+        (
+            "r3k2r/1P6/8/8/8/8/6p1/R3K2R w KQkq - 0 1",
+            "b7b8n e8g8 e1c1 g2h1r d1h1 a8b8 h1h7 b8b1 c1b1",
+        ),
+        (
+            "4k3/P7/8/3pP3/8/8/3K3p/8 w - d6 0 40",
+            "e5d6 h2h1q a7a8b h1e4 a8e4 e8d7",
+        ),
+    ];
+
+    fn game_entries() -> Vec<BinpackEntry> {
+        let mut entries = Vec::new();
+        for (game_index, (fen, moves)) in GAMES.into_iter().enumerate() {
+            let mut board = ChessBoard::from_fen(&FEN::from_str(fen));
+            for (ply, uci) in moves.split(' ').enumerate() {
+                let mv = board
+                    .parse_uci_move(uci)
+                    .unwrap_or_else(|| panic!("{uci} is illegal in {}", board.get_fen()));
+                let score = (ply as i16 - 4) * 37 * (game_index as i16 + 1);
+                let entry_board = {
+                    let mut entry_board = board;
+                    clear_unused_en_passant(&mut entry_board);
+                    entry_board
+                };
+                entries.push(BinpackEntry::new(
+                    entry_board,
+                    mv,
+                    score,
+                    ply as u16 + 10,
+                    GameResult::WhiteWin,
+                ));
+                board.apply_move(mv);
+            }
+        }
+        entries
+    }
+
+    fn assert_same_entries(result: &[BinpackEntry], expected: &[BinpackEntry]) {
+        assert_eq!(result.len(), expected.len());
+        for (result, expected) in result.iter().zip(expected) {
+            let fen = expected.board.get_fen().to_string();
+            assert_eq!(
+                result.board.get_key().get_raw(),
+                expected.board.get_key().get_raw(),
+                "{fen}"
+            );
+            assert_eq!(
+                result.board.half_move_counter(),
+                expected.board.half_move_counter(),
+                "{fen}"
+            );
+            assert!(result.mv == expected.mv, "{} in {fen}", expected.mv);
+            assert_eq!(result.score, expected.score, "{fen}");
+            assert_eq!(result.ply, expected.ply, "{fen}");
+            assert_eq!(result.result, expected.result, "{fen}");
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_entries() {
+        let entries = game_entries();
+        let bytes = write_stockfish_binpack(Vec::new(), &entries).unwrap();
+        let result = read_stockfish_binpack(bytes.as_slice()).unwrap();
+        assert_same_entries(&result, &entries);
+    }
+
+    #[test]
+    fn round_trip_restarts_chains() {
+        //Dropping every third entry breaks the chains into stems and shorter movetexts
+        let entries: Vec<BinpackEntry> = game_entries()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| index % 3 != 2)
+            .map(|(_, entry)| entry)
+            .collect();
+        let bytes = write_stockfish_binpack(Vec::new(), &entries).unwrap();
+        let result = read_stockfish_binpack(bytes.as_slice()).unwrap();
+        assert_same_entries(&result, &entries);
+    }
+
+    //1. e4 e5 from the start position with scores 16 and -10, ply 0 and a draw, encoded by
+    //following the packing code of the Stockfish training data tools
+    const UPSTREAM_FIXTURE: [u8; 44] = [
+        b'B', b'I', b'N', b'P', 0x24, 0x00, 0x00, 0x00, //chunk header
+        0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, //occupancy
+        0x2D, 0x84, 0x4A, 0xD2, 0x00, 0x00, 0x00, 0x00, //white pieces
+        0x11, 0x11, 0x11, 0x11, 0x3E, 0x95, 0x5B, 0xE3, //black pieces
+        0x0C, 0x70, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, //move, score, ply and result, rule50
+        0x00, 0x01, 0x43, 0x00, //plies in the chain, movetext
+    ];
+
+    #[test]
+    fn matches_upstream_bytes() {
+        let board = ChessBoard::from_fen(&FEN::start_position());
+        let first_move = board.parse_uci_move("e2e4").unwrap();
+        let mut second_board = board;
+        second_board.apply_move(first_move);
+        clear_unused_en_passant(&mut second_board);
+        let second_move = second_board.parse_uci_move("e7e5").unwrap();
+        let entries = [
+            BinpackEntry::new(board, first_move, 16, 0, GameResult::Draw),
+            BinpackEntry::new(second_board, second_move, -10, 1, GameResult::Draw),
+        ];
+
+        let bytes = write_stockfish_binpack(Vec::new(), &entries).unwrap();
+        assert_eq!(bytes, UPSTREAM_FIXTURE);
+        let result = read_stockfish_binpack(UPSTREAM_FIXTURE.as_slice()).unwrap();
+        assert_same_entries(&result, &entries);
+    }
+}
@@ -1,6 +1,9 @@
 use colored::Colorize;
 
-use crate::{CastleRights, ChessBoardPacked, Piece, PolicyPacked, Side, Square, FEN};
+use crate::{
+    Bitboard, CastleRights, ChessBoardPacked, ChessBoardRecord, Piece, PolicyPacked, Side, Square,
+    FEN,
+};
 
 use super::{
     chess_board_pieces::ChessBoardPieces,
@@ -131,76 +134,53 @@ impl ChessBoard {
         }
 
         *board.state.get_half_move_counter_mut() = fen.half_move_counter.parse().unwrap();
+        *board.state.get_full_move_counter_mut() = fen.full_move_counter.parse().unwrap_or(1);
 
         board
     }
 
     pub fn from_board_pack(pack: &ChessBoardPacked) -> Self {
-        let mut result = ChessBoard::default();
-        for square_index in 0..64 {
-            let square = Square::from_raw(square_index);
-            let piece = Piece::from_raw(
-                if pack.get_board()[0].get_bit(square) {
-                    1
-                } else {
-                    0
-                } | if pack.get_board()[1].get_bit(square) {
-                    2
-                } else {
-                    0
-                } | if pack.get_board()[2].get_bit(square) {
-                    4
-                } else {
-                    0
-                },
-            );
-            println!("{}", piece.get_raw());
-            if piece.get_raw() == 0b111 {
-                continue;
-            }
+        Self::from_compressed_board(pack.get_board(), pack.get_side_to_move())
+    }
 
-            if pack.get_board()[3].get_bit(square) {
-                result.set_piece_on_square::<false>(square, piece);
-            } else {
-                result.set_piece_on_square::<true>(square, piece);
-            }
-        }
+    pub fn from_policy_pack(pack: &PolicyPacked) -> Self {
+        Self::from_compressed_board(pack.get_board(), pack.get_side_to_move())
+    }
 
-        *result.state.get_side_to_move_mut() = pack.get_side_to_move();
+    pub fn from_board_record(record: &ChessBoardRecord) -> Self {
+        let mut result = Self::from_compressed_board(record.get_board(), record.get_side_to_move());
+        *result.state.get_castle_rights_mut() = record.get_castle_rights();
+        *result.state.get_en_passant_mut() = record.get_en_passant_square();
+        *result.state.get_half_move_counter_mut() = record.get_half_move_counter();
+        *result.state.get_full_move_counter_mut() = record.get_full_move_counter();
         result
     }
 
-    pub fn from_policy_pack(pack: &PolicyPacked) -> Self {
+    //Piece of each square is stored in the first three bitboards, 0b111 marks an empty square.
+    //The fourth bitboard marks black pieces.
+    fn from_compressed_board(board: &[Bitboard; 4], side_to_move: Side) -> Self {
         let mut result = ChessBoard::default();
         for square_index in 0..64 {
             let square = Square::from_raw(square_index);
             let piece = Piece::from_raw(
-                if pack.get_board()[0].get_bit(square) {
-                    1
-                } else {
-                    0
-                } | if pack.get_board()[1].get_bit(square) {
-                    2
-                } else {
-                    0
-                } | if pack.get_board()[2].get_bit(square) {
-                    4
-                } else {
-                    0
-                },
+                u8::from(board[0].get_bit(square))
+                    | u8::from(board[1].get_bit(square)) << 1
+                    | u8::from(board[2].get_bit(square)) << 2,
             );
             if piece.get_raw() == 0b111 {
                 continue;
             }
 
-            if pack.get_board()[3].get_bit(square) {
+            if board[3].get_bit(square) {
                 result.set_piece_on_square::<false>(square, piece);
             } else {
                 result.set_piece_on_square::<true>(square, piece);
             }
         }
 
-        *result.state.get_side_to_move_mut() = pack.get_side_to_move();
+        *result.state.get_side_to_move_mut() = side_to_move;
+        *result.state.get_full_move_counter_mut() = 1;
+        *result.state.get_phase_mut() = result.compute_phase_from_scratch();
         result
     }
 
@@ -277,7 +257,7 @@ impl ChessBoard {
         fen.push(' ');
         fen.push_str(&self.half_move_counter().to_string());
         fen.push(' ');
-        fen.push_str(&(1).to_string());
+        fen.push_str(&self.full_move_counter().to_string());
 
         FEN::from_string(fen)
     }
@@ -302,14 +282,7 @@ impl ChessBoard {
         info.push(en_passant.as_str());
         let half_moves = format!("Half Moves: {}", self.half_move_counter());
         info.push(half_moves.as_str());
-        let in_check = format!(
-            "In Check: {}",
-            if self.side_to_move() == Side::WHITE {
-                self.is_in_check::<true, false>()
-            } else {
-                self.is_in_check::<false, true>()
-            }
-        );
+        let in_check = format!("In Check: {}", self.is_side_to_move_in_check());
         info.push(in_check.as_str());
         let insufficient_material =
             format!("Insufficient material: {}", self.is_insufficient_material());
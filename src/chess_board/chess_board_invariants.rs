@@ -0,0 +1,263 @@
+use std::fmt::{Debug, Display, Formatter, Result};
+
+use crate::{base_structures::ZobristKey, Bitboard, CastleRights, ChessBoard, Piece, Side, Square};
+
+use super::chess_board_state::PHASE_VALUES;
+
+//Inconsistency between the redundant parts of a board
+#[derive(Clone, Copy, PartialEq)]
+pub enum InvariantError {
+    OverlappingSides,
+    OverlappingPieces(Square),
+    OccupancyMismatch,
+    KingCount(Side, u32),
+    PawnOnBackRank(Square),
+    InvalidEnPassant(Square),
+    InvalidCastleRights(CastleRights),
+    KeyMismatch {
+        stored: ZobristKey,
+        computed: ZobristKey,
+    },
+    PhaseMismatch {
+        stored: u8,
+        computed: u8,
+    },
+}
+
+impl ChessBoard {
+    //Zobrist key built from the pieces and state, ignoring the incrementally updated key
+    pub fn compute_key_from_scratch(&self) -> ZobristKey {
+        let mut key = ZobristKey::NULL;
+        for piece_index in Piece::PAWN.get_raw()..=Piece::KING.get_raw() {
+            let piece = Piece::from_raw(piece_index);
+            self.get_piece_mask_for_side::<true>(piece)
+                .map(|square| key.update_piece_hash::<true>(piece, square));
+            self.get_piece_mask_for_side::<false>(piece)
+                .map(|square| key.update_piece_hash::<false>(piece, square));
+        }
+
+        if self.en_passant_square() != Square::NULL {
+            key ^= ZobristKey::get_en_passant_seed(self.en_passant_square())
+        }
+
+        key ^ ZobristKey::get_castle_rights_seed(self.castle_rights().get_raw())
+            ^ (ZobristKey::get_side_to_move_seed() * self.side_to_move().get_raw() as u64)
+    }
+
+    pub fn compute_phase_from_scratch(&self) -> u8 {
+        let mut phase = 0;
+        for piece_index in Piece::PAWN.get_raw()..=Piece::KING.get_raw() {
+            let count = self
+                .get_piece_mask(Piece::from_raw(piece_index))
+                .pop_count() as u8;
+            phase += count * PHASE_VALUES[piece_index as usize];
+        }
+        phase
+    }
+
+    //Verifies that side occupancies, piece bitboards, key, phase, castle rights and en passant
+    //square describe the same position. It does not check whether the position is reachable.
+    pub fn check_invariants(&self) -> std::result::Result<(), InvariantError> {
+        self.check_redundant_state()?;
+
+        let white = self.get_occupancy_for_side::<true>();
+        let black = self.get_occupancy_for_side::<false>();
+        for (side, occupancy) in [(Side::WHITE, white), (Side::BLACK, black)] {
+            let kings = (self.get_piece_mask(Piece::KING) & occupancy).pop_count();
+            if kings != 1 {
+                return Err(InvariantError::KingCount(side, kings));
+            }
+        }
+
+        let back_rank_pawns =
+            self.get_piece_mask(Piece::PAWN) & (Bitboard::RANK_1 | Bitboard::RANK_8);
+        if !back_rank_pawns.is_empty() {
+            return Err(InvariantError::PawnOnBackRank(
+                back_rank_pawns.ls1b_square(),
+            ));
+        }
+
+        let en_passant = self.en_passant_square();
+        if en_passant != Square::NULL && !self.is_en_passant_consistent(en_passant) {
+            return Err(InvariantError::InvalidEnPassant(en_passant));
+        }
+
+        if !self.is_castle_rights_consistent() {
+            return Err(InvariantError::InvalidCastleRights(self.castle_rights()));
+        }
+
+        Ok(())
+    }
+
+    //Verifies only the state that is stored more than once: side occupancies against piece
+    //bitboards, and the incremental key and phase against values computed from scratch. Unlike
+    //check_invariants it holds for sloppy positions, so make_move can assert it after every move.
+    pub fn check_redundant_state(&self) -> std::result::Result<(), InvariantError> {
+        let white = self.get_occupancy_for_side::<true>();
+        let black = self.get_occupancy_for_side::<false>();
+        if !(white & black).is_empty() {
+            return Err(InvariantError::OverlappingSides);
+        }
+
+        let mut pieces = Bitboard::EMPTY;
+        for piece_index in Piece::PAWN.get_raw()..=Piece::KING.get_raw() {
+            let mask = self.get_piece_mask(Piece::from_raw(piece_index));
+            let overlap = pieces & mask;
+            if !overlap.is_empty() {
+                return Err(InvariantError::OverlappingPieces(overlap.ls1b_square()));
+            }
+            pieces |= mask;
+        }
+        if pieces != (white | black) {
+            return Err(InvariantError::OccupancyMismatch);
+        }
+
+        let computed = self.compute_key_from_scratch();
+        if self.get_key() != computed {
+            return Err(InvariantError::KeyMismatch {
+                stored: self.get_key(),
+                computed,
+            });
+        }
+
+        let computed = self.compute_phase_from_scratch();
+        if self.get_phase() != computed {
+            return Err(InvariantError::PhaseMismatch {
+                stored: self.get_phase(),
+                computed,
+            });
+        }
+
+        Ok(())
+    }
+
+    //En passant square has to be empty, on the third rank from the side that just moved,
+    //with that side's pawn right in front of it
+    fn is_en_passant_consistent(&self, square: Square) -> bool {
+        let (rank, pawn_square, pawn_side) = if self.side_to_move() == Side::WHITE {
+            (5, square ^ 8, Side::BLACK)
+        } else {
+            (2, square ^ 8, Side::WHITE)
+        };
+
+        square.get_rank() == rank
+            && self.get_piece_on_square(square) == Piece::NONE
+            && self.get_piece_on_square(pawn_square) == Piece::PAWN
+            && self.get_piece_color_on_square(pawn_square) == pawn_side
+    }
+
+    //Every castle right requires the king and the matching rook on their initial squares
+    fn is_castle_rights_consistent(&self) -> bool {
+        let rights = [
+            (
+                CastleRights::WHITE_KING,
+                Side::WHITE,
+                Square::E1,
+                Square::H1,
+            ),
+            (
+                CastleRights::WHITE_QUEEN,
+                Side::WHITE,
+                Square::E1,
+                Square::A1,
+            ),
+            (
+                CastleRights::BLACK_KING,
+                Side::BLACK,
+                Square::E8,
+                Square::H8,
+            ),
+            (
+                CastleRights::BLACK_QUEEN,
+                Side::BLACK,
+                Square::E8,
+                Square::A8,
+            ),
+        ];
+
+        rights
+            .into_iter()
+            .all(|(right, side, king_square, rook_square)| {
+                !self.castle_rights().has_right(right)
+                    || (self.is_piece_of_side(king_square, Piece::KING, side)
+                        && self.is_piece_of_side(rook_square, Piece::ROOK, side))
+            })
+    }
+
+    fn is_piece_of_side(&self, square: Square, piece: Piece, side: Side) -> bool {
+        self.get_piece_on_square(square) == piece && self.get_piece_color_on_square(square) == side
+    }
+}
+
+impl Display for InvariantError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::OverlappingSides => write!(formatter, "white and black occupancy overlap"),
+            Self::OverlappingPieces(square) => {
+                write!(formatter, "more than one piece type on {square}")
+            }
+            Self::OccupancyMismatch => {
+                write!(formatter, "piece bitboards do not match side occupancy")
+            }
+            Self::KingCount(side, count) => write!(formatter, "{side} has {count} kings"),
+            Self::PawnOnBackRank(square) => write!(formatter, "pawn on back rank square {square}"),
+            Self::InvalidEnPassant(square) => {
+                write!(formatter, "en passant square {square} has no matching pawn")
+            }
+            Self::InvalidCastleRights(rights) => write!(
+                formatter,
+                "castle rights {rights} without king and rook on their initial squares"
+            ),
+            Self::KeyMismatch { stored, computed } => write!(
+                formatter,
+                "incremental key {stored} differs from computed key {computed}"
+            ),
+            Self::PhaseMismatch { stored, computed } => write!(
+                formatter,
+                "incremental phase {stored} differs from computed phase {computed}"
+            ),
+        }
+    }
+}
+
+impl Debug for InvariantError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        Display::fmt(self, formatter)
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
+#[cfg(test)]
+mod tests {
+    use super::InvariantError;
+    use crate::{CastleRights, ChessBoard, Move, MoveFlag, Square, FEN};
+
+    #[test]
+    fn sloppy_positions_keep_redundant_state_after_moves() {
+        let mut board = ChessBoard::from_fen(&FEN::from_str("4k3/8/8/8/8/8/8/4K3 w kq - 0 1"));
+        assert_eq!(
+            board.check_invariants(),
+            Err(InvariantError::InvalidCastleRights(CastleRights::from_raw(
+                0b0011
+            )))
+        );
+
+        board.apply_move(Move::from_squares(
+            Square::E1,
+            Square::E2,
+            MoveFlag::QUIET_MOVE,
+        ));
+        assert!(board.check_redundant_state().is_ok());
+        assert!(board.check_invariants().is_err());
+    }
+
+    #[test]
+    fn legal_positions_pass_all_checks() {
+        let board = ChessBoard::from_fen(&FEN::from_str(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ));
+        assert!(board.check_redundant_state().is_ok());
+        assert!(board.check_invariants().is_ok());
+    }
+}
@@ -1,12 +1,46 @@
 use crate::{
     attacks::{Attacks, Rays},
+    base_structures::Side,
     Bitboard, ChessBoard, Piece,
 };
 
-#[derive(Clone, Copy, Default)]
-pub struct ChessBoardMasks {}
-
 impl ChessBoard {
+    #[inline]
+    pub fn is_side_to_move_in_check(&self) -> bool {
+        if self.side_to_move() == Side::WHITE {
+            self.is_in_check::<true, false>()
+        } else {
+            self.is_in_check::<false, true>()
+        }
+    }
+
+    //Pieces giving check to the side to move
+    #[inline]
+    pub fn get_checkers(&self) -> Bitboard {
+        if self.side_to_move() == Side::WHITE {
+            self.generate_checkers_mask::<true, false>()
+        } else {
+            self.generate_checkers_mask::<false, true>()
+        }
+    }
+
+    //Pieces of the side to move that are pinned to their king
+    pub fn get_pinned_pieces(&self) -> Bitboard {
+        let (diagonal_pins, ortographic_pins) = if self.side_to_move() == Side::WHITE {
+            self.generate_pin_masks::<true, false>()
+        } else {
+            self.generate_pin_masks::<false, true>()
+        };
+
+        let defender_occupancy = if self.side_to_move() == Side::WHITE {
+            self.get_occupancy_for_side::<true>()
+        } else {
+            self.get_occupancy_for_side::<false>()
+        };
+
+        (diagonal_pins | ortographic_pins) & defender_occupancy
+    }
+
     #[inline]
     pub fn is_in_check<const DEFENDER_WHITE: bool, const ATTACKER_WHITE: bool>(&self) -> bool {
         self.is_square_attacked::<DEFENDER_WHITE, ATTACKER_WHITE>(
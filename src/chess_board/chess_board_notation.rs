@@ -0,0 +1,185 @@
+use crate::{ChessBoard, Move, MoveFlag, Piece};
+
+impl ChessBoard {
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.map_legal_moves(|mv| moves.push(mv));
+        moves
+    }
+
+    //Finds the legal move matching a move in long algebraic notation (e2e4, e7e8q)
+    pub fn parse_uci_move(&self, uci: &str) -> Option<Move> {
+        let uci = uci.trim();
+        self.get_legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+    }
+
+    //Finds the legal move matching a move in standard algebraic notation.
+    //Check, mate and annotation suffixes are ignored, capture and promotion signs are optional.
+    pub fn parse_san_move(&self, san: &str) -> Option<Move> {
+        let san = normalize_san(san);
+        if san.is_empty() {
+            return None;
+        }
+
+        self.get_legal_moves()
+            .into_iter()
+            .find(|&mv| normalize_san(&self.move_to_san(mv)) == san)
+    }
+
+    //Formats a legal move in standard algebraic notation, including check and mate suffixes
+    pub fn move_to_san(&self, mv: Move) -> String {
+        let mut san = String::new();
+        let from_square = mv.get_from_square();
+        let to_square = mv.get_to_square();
+        let piece = self.get_piece_on_square(from_square);
+
+        match mv.get_flag() {
+            MoveFlag::KING_SIDE_CASTLE => san.push_str("O-O"),
+            MoveFlag::QUEEN_SIDE_CASTLE => san.push_str("O-O-O"),
+            _ if piece == Piece::PAWN => {
+                if mv.is_capture() {
+                    san.push((b'a' + from_square.get_file()) as char);
+                    san.push('x');
+                }
+                san.push_str(&to_square.to_string());
+                if mv.is_promotion() {
+                    san.push('=');
+                    san.push(mv.get_promotion_piece().to_char().to_ascii_uppercase());
+                }
+            }
+            _ => {
+                san.push(piece.to_char().to_ascii_uppercase());
+
+                let mut ambiguous = false;
+                let mut same_file = false;
+                let mut same_rank = false;
+                self.map_legal_moves(|other| {
+                    let other_from_square = other.get_from_square();
+                    if other.get_to_square() != to_square
+                        || other_from_square == from_square
+                        || self.get_piece_on_square(other_from_square) != piece
+                    {
+                        return;
+                    }
+
+                    ambiguous = true;
+                    same_file |= other_from_square.get_file() == from_square.get_file();
+                    same_rank |= other_from_square.get_rank() == from_square.get_rank();
+                });
+
+                if ambiguous {
+                    if !same_file {
+                        san.push((b'a' + from_square.get_file()) as char);
+                    } else if !same_rank {
+                        san.push((b'1' + from_square.get_rank()) as char);
+                    } else {
+                        san.push_str(&from_square.to_string());
+                    }
+                }
+
+                if mv.is_capture() {
+                    san.push('x');
+                }
+                san.push_str(&to_square.to_string());
+            }
+        }
+
+        let mut board_copy = *self;
+        board_copy.apply_move(mv);
+        if board_copy.is_side_to_move_in_check() {
+            let mut has_moves = false;
+            board_copy.map_legal_moves(|_| has_moves = true);
+            san.push(if has_moves { '+' } else { '#' });
+        }
+
+        san
+    }
+}
+
+//Promotion letters are uppercased, so e8q and e8=q match e8=Q
+fn normalize_san(san: &str) -> String {
+    let mut result: Vec<char> = san
+        .trim()
+        .replace('0', "O")
+        .chars()
+        .filter(|character| !matches!(character, '+' | '#' | '!' | '?' | 'x' | '=' | ':'))
+        .collect();
+
+    if let [.., rank, promotion] = result.as_mut_slice() {
+        if rank.is_ascii_digit() && matches!(promotion, 'q' | 'r' | 'b' | 'n') {
+            promotion.make_ascii_uppercase();
+        }
+    }
+    result.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChessBoard, FEN};
+
+    const FENS: [&str; 3] = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ];
+
+    #[test]
+    fn san_round_trips_every_legal_move() {
+        for fen in FENS {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            for mv in board.get_legal_moves() {
+                let san = board.move_to_san(mv);
+                assert!(board.parse_san_move(&san) == Some(mv), "{fen} {san}");
+                assert!(
+                    board.parse_uci_move(&mv.to_string()) == Some(mv),
+                    "{fen} {mv}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn san_notation() {
+        let board = ChessBoard::from_fen(&FEN::kiwipete_position());
+        let san = |uci| board.move_to_san(board.parse_uci_move(uci).unwrap());
+        assert_eq!(san("e1g1"), "O-O");
+        assert_eq!(san("e1c1"), "O-O-O");
+        assert_eq!(san("e5f7"), "Nxf7");
+        assert_eq!(san("d5e6"), "dxe6");
+        assert_eq!(san("d2c1"), "Bc1");
+
+        let board = ChessBoard::from_fen(&FEN::from_str("4k3/8/1Q6/R7/8/8/8/RN1QKN2 w - - 0 1"));
+        let san = |uci| board.move_to_san(board.parse_uci_move(uci).unwrap());
+        assert_eq!(san("b1d2"), "Nbd2");
+        assert_eq!(san("f1d2"), "Nfd2");
+        assert_eq!(san("a1a3"), "R1a3");
+        assert_eq!(san("a5a3"), "R5a3");
+        assert_eq!(san("a5a6"), "Ra6");
+        assert_eq!(san("b6d4"), "Qbd4");
+        assert_eq!(san("d1d4"), "Qdd4");
+
+        let board = ChessBoard::from_fen(&FEN::from_str("6k1/3P4/8/8/8/8/8/R3K2R w - - 0 1"));
+        let san = |uci| board.move_to_san(board.parse_uci_move(uci).unwrap());
+        assert_eq!(san("d7d8q"), "d8=Q+");
+        assert_eq!(san("d7d8n"), "d8=N");
+        assert_eq!(san("a1a8"), "Ra8+");
+        assert_eq!(san("h1h8"), "Rh8+");
+    }
+
+    #[test]
+    fn san_parsing_is_lenient() {
+        let board = ChessBoard::from_fen(&FEN::from_str("6k1/3P4/8/8/8/8/8/R3K2R w KQ - 0 1"));
+        let uci = |san| board.parse_san_move(san).map(|mv| mv.to_string());
+        for san in ["d8=Q+", "d8Q", "d8q", "d8=q", "d8=Q!?"] {
+            assert_eq!(uci(san).as_deref(), Some("d7d8q"), "{san}");
+        }
+        assert_eq!(uci("d8=n").as_deref(), Some("d7d8n"));
+        assert_eq!(uci("d8b").as_deref(), Some("d7d8b"));
+        assert_eq!(uci("0-0").as_deref(), Some("e1g1"));
+        assert_eq!(uci("O-O-O").as_deref(), Some("e1c1"));
+        assert_eq!(uci("d8"), None);
+        assert_eq!(uci(""), None);
+    }
+}
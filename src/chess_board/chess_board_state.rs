@@ -9,6 +9,7 @@ pub(super) const PHASE_VALUES: [u8; 6] = [0, 1, 1, 2, 4, 0];
 pub struct ChessBoardState {
     zobrist: ZobristKey,
     half_moves: u8,
+    full_moves: u16,
     en_passant: Square,
     side_to_move: Side,
     castle_rights: CastleRights,
@@ -41,6 +42,11 @@ impl ChessBoardState {
         &mut self.half_moves
     }
 
+    #[inline]
+    pub(super) fn get_full_move_counter_mut(&mut self) -> &mut u16 {
+        &mut self.full_moves
+    }
+
     #[inline]
     pub(super) fn get_phase_mut(&mut self) -> &mut u8 {
         &mut self.phase
@@ -48,6 +54,34 @@ impl ChessBoardState {
 }
 
 impl ChessBoard {
+    //Setters for decoders assembling a board piece by piece, the caller is responsible
+    //for leaving the board in a consistent state
+    #[inline]
+    pub(crate) fn set_side_to_move(&mut self, side_to_move: Side) {
+        self.state.side_to_move = side_to_move
+    }
+
+    #[inline]
+    pub(crate) fn set_castle_rights(&mut self, castle_rights: CastleRights) {
+        self.state.castle_rights = castle_rights
+    }
+
+    #[inline]
+    pub(crate) fn set_en_passant_square(&mut self, square: Square) {
+        self.state.en_passant = square
+    }
+
+    #[inline]
+    pub(crate) fn set_move_counters(&mut self, half_moves: u8, full_moves: u16) {
+        self.state.half_moves = half_moves;
+        self.state.full_moves = full_moves;
+    }
+
+    #[inline]
+    pub(crate) fn refresh_phase(&mut self) {
+        self.state.phase = self.compute_phase_from_scratch()
+    }
+
     #[inline]
     pub fn get_key(&self) -> ZobristKey {
         let mut key = self.state.zobrist;
@@ -80,6 +114,11 @@ impl ChessBoard {
         self.state.half_moves
     }
 
+    #[inline]
+    pub fn full_move_counter(&self) -> u16 {
+        self.state.full_moves
+    }
+
     #[inline]
     pub fn get_phase(&self) -> u8 {
         self.state.phase
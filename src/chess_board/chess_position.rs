@@ -2,21 +2,12 @@ use crate::{Move, MoveHistory, FEN};
 
 use super::ChessBoard;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub struct ChessPosition {
     board: ChessBoard,
     history: MoveHistory,
 }
 
-impl Default for ChessPosition {
-    fn default() -> Self {
-        Self {
-            board: ChessBoard::default(),
-            history: MoveHistory::new(),
-        }
-    }
-}
-
 impl ChessPosition {
     #[inline]
     pub fn from_fen(fen: &FEN) -> Self {
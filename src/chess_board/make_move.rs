@@ -1,4 +1,4 @@
-use crate::{base_structures::Move, CastleRights, ChessBoard, MoveFlag, Piece, Square};
+use crate::{base_structures::Move, CastleRights, ChessBoard, MoveFlag, Piece, Side, Square};
 
 use super::chess_board_state::PHASE_VALUES;
 
@@ -9,7 +9,21 @@ impl ChessBoard {
             mv,
             mv.get_from_square(),
             mv.get_to_square(),
-        )
+        );
+
+        #[cfg(debug_assertions)]
+        if let Err(error) = self.check_redundant_state() {
+            panic!("{mv} broke board state: {error}");
+        }
+    }
+
+    #[inline]
+    pub fn apply_move(&mut self, mv: Move) {
+        if self.side_to_move() == Side::WHITE {
+            self.make_move::<true, false>(mv)
+        } else {
+            self.make_move::<false, true>(mv)
+        }
     }
 
     #[inline]
@@ -229,6 +243,10 @@ impl ChessBoard {
             _ => {}
         }
 
+        if !STM_WHITE {
+            *self.state.get_full_move_counter_mut() += 1;
+        }
+
         self.state.get_side_to_move_mut().mut_flip();
     }
 }
@@ -1,9 +1,15 @@
 mod chess_board_base;
+mod chess_board_invariants;
 mod chess_board_masks;
+mod chess_board_notation;
 mod chess_board_pieces;
 mod chess_board_state;
 mod chess_position;
 mod make_move;
+mod move_delta;
+mod see;
 
 pub use chess_board_base::ChessBoard;
+pub use chess_board_invariants::InvariantError;
 pub use chess_position::ChessPosition;
+pub use move_delta::{MoveDelta, PieceSquare};
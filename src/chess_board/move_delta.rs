@@ -0,0 +1,293 @@
+use crate::{base_structures::Side, CastleRights, ChessBoard, Move, MoveFlag, Piece, Square};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct PieceSquare {
+    pub piece: Piece,
+    pub side: Side,
+    pub square: Square,
+}
+
+impl Default for PieceSquare {
+    fn default() -> Self {
+        Self {
+            piece: Piece::NONE,
+            side: Side::WHITE,
+            square: Square::NULL,
+        }
+    }
+}
+
+//Pieces a move takes off and puts on the board, at most two of each:
+//quiet moves 1/1, captures and promotions 2/1 or 1/1, en passant 2/1 and castling 2/2
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct MoveDelta {
+    removed: [PieceSquare; 2],
+    removed_count: u8,
+    added: [PieceSquare; 2],
+    added_count: u8,
+    moving_side: Side,
+    king_move: bool,
+}
+
+impl MoveDelta {
+    #[inline]
+    pub fn removed(&self) -> &[PieceSquare] {
+        &self.removed[..self.removed_count as usize]
+    }
+
+    #[inline]
+    pub fn added(&self) -> &[PieceSquare] {
+        &self.added[..self.added_count as usize]
+    }
+
+    #[inline]
+    pub fn moving_side(&self) -> Side {
+        self.moving_side
+    }
+
+    //King moves and castling change the king square, which king relative features of the
+    //moving side's perspective have to rebuild from scratch
+    #[inline]
+    pub fn is_king_move(&self) -> bool {
+        self.king_move
+    }
+
+    #[inline]
+    fn remove(&mut self, piece: Piece, side: Side, square: Square) {
+        self.removed[self.removed_count as usize] = PieceSquare {
+            piece,
+            side,
+            square,
+        };
+        self.removed_count += 1;
+    }
+
+    #[inline]
+    fn add(&mut self, piece: Piece, side: Side, square: Square) {
+        self.added[self.added_count as usize] = PieceSquare {
+            piece,
+            side,
+            square,
+        };
+        self.added_count += 1;
+    }
+}
+
+impl ChessBoard {
+    //Makes a legal move and reports the pieces it moved
+    #[inline]
+    pub fn make_move_with_delta(&mut self, mv: Move) -> MoveDelta {
+        let delta = self.move_delta(mv);
+        self.apply_move(mv);
+        delta
+    }
+
+    //Delta the move would cause, the board is left untouched
+    pub fn move_delta(&self, mv: Move) -> MoveDelta {
+        let side = self.side_to_move();
+        let from_square = mv.get_from_square();
+        let to_square = mv.get_to_square();
+        let moved_piece = self.get_piece_on_square(from_square);
+
+        let mut delta = MoveDelta {
+            moving_side: side,
+            king_move: moved_piece == Piece::KING,
+            ..Default::default()
+        };
+
+        delta.remove(moved_piece, side, from_square);
+        if mv.is_en_passant() {
+            delta.remove(Piece::PAWN, side.flipped(), to_square ^ 8);
+        } else if mv.is_capture() {
+            delta.remove(
+                self.get_piece_on_square(to_square),
+                side.flipped(),
+                to_square,
+            );
+        }
+
+        let placed_piece = if mv.is_promotion() {
+            mv.get_promotion_piece()
+        } else {
+            moved_piece
+        };
+        delta.add(placed_piece, side, to_square);
+
+        if mv.is_castle() {
+            let king_side = usize::from(mv.get_flag() == MoveFlag::KING_SIDE_CASTLE);
+            let side_flip = 56 * side.get_raw();
+            delta.remove(
+                Piece::ROOK,
+                side,
+                Square::from_raw(side_flip + CastleRights::ROOK_POSITIONS[king_side]),
+            );
+            delta.add(
+                Piece::ROOK,
+                side,
+                Square::from_raw(side_flip + [3, 5][king_side]),
+            );
+        }
+
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PieceSquare;
+    use crate::{
+        features::{FeatureSet, KingBuckets},
+        ChessBoard, MoveFlag, Piece, Random, Side, FEN,
+    };
+
+    const FENS: [&str; 4] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ];
+
+    fn sorted(mut pieces: Vec<(u8, u8, u8)>) -> Vec<(u8, u8, u8)> {
+        pieces.sort_unstable();
+        pieces
+    }
+
+    fn raw(entries: &[PieceSquare]) -> Vec<(u8, u8, u8)> {
+        sorted(
+            entries
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.square.get_raw(),
+                        entry.piece.get_raw(),
+                        entry.side.get_raw(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    //(square, piece, side) of every occupied square
+    fn pieces(board: &ChessBoard) -> Vec<(u8, u8, u8)> {
+        let mut result = Vec::new();
+        board.get_occupancy().map(|square| {
+            result.push((
+                square.get_raw(),
+                board.get_piece_on_square(square).get_raw(),
+                board.get_piece_color_on_square(square).get_raw(),
+            ))
+        });
+        result
+    }
+
+    fn sorted_features(mut features: Vec<usize>) -> Vec<usize> {
+        features.sort_unstable();
+        features
+    }
+
+    #[test]
+    fn delta_matches_board_diff() {
+        let feature_sets = [
+            FeatureSet::Chess768,
+            FeatureSet::HalfKP,
+            FeatureSet::HalfKA(KingBuckets::per_square(true)),
+        ];
+        let mut random = Random::from_seed(46);
+        let mut counts = [0usize; 6];
+
+        for fen in FENS {
+            for _ in 0..4 {
+                let mut board = ChessBoard::from_fen(&FEN::from_str(fen));
+                for _ in 0..30 {
+                    let moves = board.get_legal_moves();
+                    if moves.is_empty() {
+                        break;
+                    }
+
+                    let before = pieces(&board);
+                    for &mv in &moves {
+                        let delta = board.move_delta(mv);
+                        let mut after_board = board;
+                        after_board.apply_move(mv);
+                        let after = pieces(&after_board);
+
+                        let removed: Vec<_> = before
+                            .iter()
+                            .filter(|entry| !after.contains(entry))
+                            .copied()
+                            .collect();
+                        let added: Vec<_> = after
+                            .iter()
+                            .filter(|entry| !before.contains(entry))
+                            .copied()
+                            .collect();
+                        assert!(raw(delta.removed()) == sorted(removed), "{fen} {mv}");
+                        assert!(raw(delta.added()) == sorted(added), "{fen} {mv}");
+                        assert!(delta.moving_side() == board.side_to_move());
+                        assert_eq!(
+                            delta.is_king_move(),
+                            board.get_piece_on_square(mv.get_from_square()) == Piece::KING
+                        );
+
+                        for feature_set in &feature_sets {
+                            for perspective in [Side::WHITE, Side::BLACK] {
+                                let refresh = feature_set.requires_refresh(&delta, perspective);
+                                if !delta.is_king_move() || delta.moving_side() != perspective {
+                                    assert!(!refresh);
+                                }
+                                if *feature_set == FeatureSet::HalfKP {
+                                    assert_eq!(
+                                        refresh,
+                                        delta.is_king_move() && delta.moving_side() == perspective
+                                    );
+                                }
+                                if refresh {
+                                    continue;
+                                }
+
+                                let mut features = feature_set.active_features(&board, perspective);
+                                let mut added = Vec::new();
+                                let mut removed = Vec::new();
+                                feature_set.map_delta_features(
+                                    &delta,
+                                    &after_board,
+                                    perspective,
+                                    |index| added.push(index),
+                                    |index| removed.push(index),
+                                );
+                                for index in removed {
+                                    let position =
+                                        features.iter().position(|&f| f == index).unwrap();
+                                    features.swap_remove(position);
+                                }
+                                features.extend(added);
+                                assert_eq!(
+                                    sorted_features(features),
+                                    sorted_features(
+                                        feature_set.active_features(&after_board, perspective)
+                                    ),
+                                    "{fen} {mv}"
+                                );
+                            }
+                        }
+
+                        let kind = match mv.get_flag() {
+                            MoveFlag::KING_SIDE_CASTLE => 3,
+                            MoveFlag::QUEEN_SIDE_CASTLE => 4,
+                            _ if mv.is_en_passant() => 2,
+                            _ if mv.is_promotion() && mv.is_capture() => 5,
+                            _ if mv.is_capture() => 1,
+                            _ => 0,
+                        };
+                        counts[kind] += 1;
+                    }
+
+                    board.apply_move(moves[random.next_range(moves.len() as u64) as usize]);
+                }
+            }
+        }
+
+        //Quiet moves, captures, en passant, O-O, O-O-O and capture promotions
+        assert!(counts.iter().all(|&count| count > 0), "{counts:?}");
+    }
+}
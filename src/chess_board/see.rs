@@ -0,0 +1,155 @@
+use crate::{base_structures::Side, Bitboard, ChessBoard, Move, Piece, Square};
+
+impl ChessBoard {
+    //Pawn, knight, bishop, rook, queen and king values of the exchange evaluation
+    pub const SEE_VALUES: [i32; 6] = [100, 300, 300, 500, 900, 0];
+
+    //Material the side to move wins with the move when both sides keep recapturing on the
+    //destination square with their least valuable attacker and may stop at any point.
+    //Sliders behind the capturing pieces join in, pins are ignored, and promotions only count
+    //for the move itself.
+    pub fn static_exchange_evaluation(&self, mv: Move) -> i32 {
+        let from_square = mv.get_from_square();
+        let to_square = mv.get_to_square();
+        if mv.is_castle() {
+            return 0;
+        }
+
+        let mut occupancy = self.get_occupancy().exclude(from_square);
+        let mut gains = [0; 32];
+        gains[0] = if mv.is_en_passant() {
+            occupancy = occupancy.exclude(to_square ^ 8);
+            see_value(Piece::PAWN)
+        } else if mv.is_capture() {
+            see_value(self.get_piece_on_square(to_square))
+        } else {
+            0
+        };
+
+        let mut piece_on_square = self.get_piece_on_square(from_square);
+        if mv.is_promotion() {
+            piece_on_square = mv.get_promotion_piece();
+            gains[0] += see_value(piece_on_square) - see_value(Piece::PAWN);
+        }
+
+        let mut side = self.side_to_move().flipped();
+        let mut depth = 0;
+        loop {
+            let attackers = self.attackers_of_both_sides(to_square, occupancy) & occupancy;
+            let side_attackers = attackers & self.side_occupancy(side);
+            if side_attackers.is_empty() {
+                break;
+            }
+
+            let (piece, square) = least_valuable_attacker(self, side_attackers);
+            //A king may only capture when the other side has nothing left to recapture with
+            if piece == Piece::KING
+                && (attackers & self.side_occupancy(side.flipped())).is_not_empty()
+            {
+                break;
+            }
+
+            depth += 1;
+            gains[depth] = see_value(piece_on_square) - gains[depth - 1];
+            if depth == gains.len() - 1 {
+                break;
+            }
+
+            occupancy = occupancy.exclude(square);
+            piece_on_square = piece;
+            side = side.flipped();
+        }
+
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+        gains[0]
+    }
+
+    #[inline]
+    pub fn see_at_least(&self, mv: Move, threshold: i32) -> bool {
+        self.static_exchange_evaluation(mv) >= threshold
+    }
+
+    fn attackers_of_both_sides(&self, square: Square, occupancy: Bitboard) -> Bitboard {
+        self.all_attackers_to_square::<true, false>(occupancy, square)
+            | self.all_attackers_to_square::<false, true>(occupancy, square)
+    }
+
+    fn side_occupancy(&self, side: Side) -> Bitboard {
+        if side == Side::WHITE {
+            self.get_occupancy_for_side::<true>()
+        } else {
+            self.get_occupancy_for_side::<false>()
+        }
+    }
+}
+
+#[inline]
+fn see_value(piece: Piece) -> i32 {
+    ChessBoard::SEE_VALUES[piece.get_raw() as usize]
+}
+
+fn least_valuable_attacker(board: &ChessBoard, attackers: Bitboard) -> (Piece, Square) {
+    for piece_index in Piece::PAWN.get_raw()..=Piece::KING.get_raw() {
+        let piece = Piece::from_raw(piece_index);
+        let pieces = attackers & board.get_piece_mask(piece);
+        if pieces.is_not_empty() {
+            return (piece, pieces.ls1b_square());
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChessBoard, FEN};
+
+    fn see(fen: &str, uci: &str) -> i32 {
+        let board = ChessBoard::from_fen(&FEN::from_str(fen));
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.static_exchange_evaluation(mv)
+    }
+
+    #[test]
+    fn captures() {
+        //Undefended pawn, then defended by a pawn
+        assert_eq!(see("4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1", "d1d5"), 100);
+        assert_eq!(see("4k3/8/4p3/3p4/8/8/8/3RK3 w - - 0 1", "d1d5"), -400);
+        //PxN defended by a pawn
+        assert_eq!(see("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5"), 200);
+        //NxB with the bishop defended by a rook and the knight backed by a bishop
+        assert_eq!(see("3rk3/8/8/3b4/8/2N2B2/8/4K3 w - - 0 1", "c3d5"), 300);
+        //Quiet move onto a square a pawn attacks
+        assert_eq!(see("4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1", "d1d5"), -900);
+        assert_eq!(see("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", "d1d5"), 0);
+    }
+
+    #[test]
+    fn x_ray_attackers_join_the_exchange() {
+        //The d1 rook recaptures through the d2 rook once it has moved
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+        //Queen behind a bishop
+        assert_eq!(see("4k3/6p1/5p2/8/8/2B5/1Q6/4K3 w - - 0 1", "c3f6"), -100);
+        //Black's rooks stacked behind each other outnumber the single white rook
+        assert_eq!(see("3rk3/3r4/8/3p4/8/8/8/3RK3 w - - 0 1", "d1d5"), -400);
+    }
+
+    #[test]
+    fn king_only_recaptures_undefended_pieces() {
+        assert_eq!(see("8/8/4k3/3p4/8/8/8/3RK3 w - - 0 1", "d1d5"), -400);
+        assert_eq!(see("8/8/4k3/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+    }
+
+    #[test]
+    fn en_passant_and_promotions() {
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6"), 100);
+        assert_eq!(see("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6"), 0);
+        assert_eq!(see("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), 800);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), -100);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q"), 1300);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n"), -100);
+    }
+}
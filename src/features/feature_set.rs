@@ -0,0 +1,283 @@
+use crate::{ChessBoard, MoveDelta, Piece, PieceSquare, Side, Square};
+
+use super::KingBuckets;
+
+//Sparse NNUE input features of one perspective. Pieces are split into the perspective's own
+//pieces and the opponent's, and black's perspective sees the board flipped vertically unless
+//stated otherwise, so both perspectives share the same weights.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FeatureSet {
+    //(opponent piece) * 384 + piece * 64 + relative square, 768 inputs
+    Chess768,
+    //Stockfish HalfKP: 1 + relative square + (piece * 2 + opponent piece) * 64 +
+    //relative king square * 641, 41024 inputs. Kings are not features, and black's
+    //perspective rotates the board by 180 degrees like nnue-pytorch does.
+    HalfKP,
+    //king bucket * 768 + the Chess768 index of the optionally mirrored board
+    HalfKA(KingBuckets),
+}
+
+const HALF_KP_PLANES: usize = 10 * 64 + 1;
+
+impl FeatureSet {
+    pub fn input_size(&self) -> usize {
+        match self {
+            Self::Chess768 => 768,
+            Self::HalfKP => 64 * HALF_KP_PLANES,
+            Self::HalfKA(buckets) => buckets.bucket_count() * 768,
+        }
+    }
+
+    //Upper bound of the active features of one perspective
+    pub fn max_active_features(&self) -> usize {
+        match self {
+            Self::HalfKP => 30,
+            _ => 32,
+        }
+    }
+
+    //Feature of a single piece, None when the feature set ignores the piece.
+    //The king square is the perspective's own king.
+    pub fn feature_index(
+        &self,
+        perspective: Side,
+        king_square: Square,
+        piece: Piece,
+        piece_side: Side,
+        square: Square,
+    ) -> Option<usize> {
+        let opponent = usize::from(piece_side != perspective);
+        match self {
+            Self::Chess768 => Some(
+                opponent * 384
+                    + piece.get_raw() as usize * 64
+                    + flip_for(perspective, square).get_raw() as usize,
+            ),
+            Self::HalfKP => {
+                if piece == Piece::KING {
+                    return None;
+                }
+
+                Some(
+                    1 + rotate_for(perspective, square)
+                        + (piece.get_raw() as usize * 2 + opponent) * 64
+                        + rotate_for(perspective, king_square) * HALF_KP_PLANES,
+                )
+            }
+            Self::HalfKA(buckets) => {
+                let relative_king_square = flip_for(perspective, king_square);
+                let mut relative_square = flip_for(perspective, square).get_raw();
+                if buckets.mirrors(relative_king_square) {
+                    relative_square ^= 7;
+                }
+
+                Some(
+                    buckets.bucket(relative_king_square) * 768
+                        + opponent * 384
+                        + piece.get_raw() as usize * 64
+                        + relative_square as usize,
+                )
+            }
+        }
+    }
+
+    pub fn map_features<F: FnMut(usize)>(
+        &self,
+        board: &ChessBoard,
+        perspective: Side,
+        mut method: F,
+    ) {
+        let king_square = if perspective == Side::WHITE {
+            board.get_king_square::<true>()
+        } else {
+            board.get_king_square::<false>()
+        };
+
+        board.get_occupancy().map(|square| {
+            if let Some(index) = self.feature_index(
+                perspective,
+                king_square,
+                board.get_piece_on_square(square),
+                board.get_piece_color_on_square(square),
+                square,
+            ) {
+                method(index)
+            }
+        });
+    }
+
+    pub fn active_features(&self, board: &ChessBoard, perspective: Side) -> Vec<usize> {
+        let mut result = Vec::with_capacity(self.max_active_features());
+        self.map_features(board, perspective, |index| result.push(index));
+        result
+    }
+
+    //Whether the perspective has to rebuild its features after the move instead of applying
+    //the delta, which happens when its own king changes the king square, bucket or mirroring
+    pub fn requires_refresh(&self, delta: &MoveDelta, perspective: Side) -> bool {
+        if !delta.is_king_move() || delta.moving_side() != perspective {
+            return false;
+        }
+
+        match self {
+            Self::Chess768 => false,
+            Self::HalfKP => true,
+            Self::HalfKA(buckets) => {
+                let king_square = |pieces: &[PieceSquare]| {
+                    let king = pieces.iter().find(|entry| entry.piece == Piece::KING);
+                    flip_for(perspective, king.unwrap().square)
+                };
+                let old_king_square = king_square(delta.removed());
+                let new_king_square = king_square(delta.added());
+                buckets.bucket(old_king_square) != buckets.bucket(new_king_square)
+                    || buckets.mirrors(old_king_square) != buckets.mirrors(new_king_square)
+            }
+        }
+    }
+
+    //Features the move adds and removes for a perspective that does not require a refresh,
+    //the board is the position after the move
+    pub fn map_delta_features<A: FnMut(usize), R: FnMut(usize)>(
+        &self,
+        delta: &MoveDelta,
+        board: &ChessBoard,
+        perspective: Side,
+        mut added: A,
+        mut removed: R,
+    ) {
+        let king_square = if perspective == Side::WHITE {
+            board.get_king_square::<true>()
+        } else {
+            board.get_king_square::<false>()
+        };
+
+        let index = |entry: &PieceSquare| {
+            self.feature_index(
+                perspective,
+                king_square,
+                entry.piece,
+                entry.side,
+                entry.square,
+            )
+        };
+        delta
+            .removed()
+            .iter()
+            .filter_map(index)
+            .for_each(&mut removed);
+        delta.added().iter().filter_map(index).for_each(&mut added);
+    }
+}
+
+impl ChessBoard {
+    //Active features of the side to move and of the other side
+    pub fn input_features(&self, feature_set: &FeatureSet) -> (Vec<usize>, Vec<usize>) {
+        (
+            feature_set.active_features(self, self.side_to_move()),
+            feature_set.active_features(self, self.side_to_move().flipped()),
+        )
+    }
+}
+
+#[inline]
+fn flip_for(perspective: Side, square: Square) -> Square {
+    if perspective == Side::WHITE {
+        square
+    } else {
+        square.flip()
+    }
+}
+
+#[inline]
+fn rotate_for(perspective: Side, square: Square) -> usize {
+    if perspective == Side::WHITE {
+        square.get_raw() as usize
+    } else {
+        63 - square.get_raw() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureSet;
+    use crate::{features::KingBuckets, ChessBoard, Piece, Side, Square, FEN};
+
+    #[test]
+    fn chess768_indices() {
+        let features = FeatureSet::Chess768;
+        let index = |perspective, piece, side, square| {
+            features.feature_index(perspective, Square::E1, piece, side, square)
+        };
+
+        assert_eq!(
+            index(Side::WHITE, Piece::KNIGHT, Side::WHITE, Square::G1),
+            Some(70)
+        );
+        assert_eq!(
+            index(Side::BLACK, Piece::KNIGHT, Side::WHITE, Square::G1),
+            Some(510)
+        );
+        assert_eq!(
+            index(Side::BLACK, Piece::PAWN, Side::BLACK, Square::E7),
+            Some(12)
+        );
+        assert_eq!(
+            index(Side::WHITE, Piece::KING, Side::BLACK, Square::E8),
+            Some(764)
+        );
+    }
+
+    //Values of halfkp_idx from nnue-pytorch, which orients squares with sq ^ 63 for black
+    #[test]
+    fn half_kp_matches_nnue_pytorch() {
+        let features = FeatureSet::HalfKP;
+        let white = |piece, side, square| {
+            features.feature_index(Side::WHITE, Square::E1, piece, side, square)
+        };
+        let black = |piece, side, square| {
+            features.feature_index(Side::BLACK, Square::E8, piece, side, square)
+        };
+
+        assert_eq!(white(Piece::PAWN, Side::WHITE, Square::E2), Some(2577));
+        assert_eq!(white(Piece::PAWN, Side::BLACK, Square::E7), Some(2681));
+        assert_eq!(black(Piece::PAWN, Side::BLACK, Square::E7), Some(1935));
+        assert_eq!(black(Piece::QUEEN, Side::WHITE, Square::D1), Some(2560));
+        assert_eq!(white(Piece::KING, Side::BLACK, Square::E8), None);
+        assert_eq!(features.input_size(), 41024);
+    }
+
+    #[test]
+    fn half_ka_mirrors_black_king_on_the_kingside() {
+        let features = FeatureSet::HalfKA(KingBuckets::per_square(true));
+        assert_eq!(features.input_size(), 32 * 768);
+
+        //g8 is g1 for black, mirrored to b1 which is bucket 1
+        let kingside = |piece, side, square| {
+            features.feature_index(Side::BLACK, Square::G8, piece, side, square)
+        };
+        assert_eq!(kingside(Piece::KNIGHT, Side::BLACK, Square::F6), Some(850));
+        assert_eq!(kingside(Piece::PAWN, Side::WHITE, Square::E4), Some(1187));
+
+        //d8 is d1 for black and stays unmirrored in bucket 3
+        let queenside = |piece, side, square| {
+            features.feature_index(Side::BLACK, Square::D8, piece, side, square)
+        };
+        assert_eq!(
+            queenside(Piece::KNIGHT, Side::BLACK, Square::F6),
+            Some(2389)
+        );
+    }
+
+    #[test]
+    fn single_bucket_half_ka_equals_chess768() {
+        let board = ChessBoard::from_fen(&FEN::from_str(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ));
+        for perspective in [Side::WHITE, Side::BLACK] {
+            assert_eq!(
+                FeatureSet::HalfKA(KingBuckets::single()).active_features(&board, perspective),
+                FeatureSet::Chess768.active_features(&board, perspective)
+            );
+        }
+    }
+}
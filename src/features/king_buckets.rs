@@ -0,0 +1,96 @@
+use crate::Square;
+
+//Maps the perspective's king square to an input bucket. The layout is indexed by the king
+//square from the perspective's point of view (a1 = 0, own back rank first). With mirroring,
+//kings on files e-h use the bucket of the horizontally mirrored square, so only files a-d of
+//the layout are used, and the whole board is mirrored for that perspective.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KingBuckets {
+    layout: [u8; 64],
+    mirrored: bool,
+    bucket_count: usize,
+}
+
+impl KingBuckets {
+    pub fn new(layout: [u8; 64], mirrored: bool) -> Result<Self, String> {
+        let mut used = [false; 256];
+        for (square_index, &bucket) in layout.iter().enumerate() {
+            if !mirrored || square_index % 8 < 4 {
+                used[bucket as usize] = true;
+            }
+        }
+
+        let bucket_count = used.iter().rposition(|&used| used).unwrap() + 1;
+        if let Some(missing) = used[..bucket_count].iter().position(|&used| !used) {
+            return Err(format!("bucket {missing} is not used by any king square"));
+        }
+
+        Ok(Self {
+            layout,
+            mirrored,
+            bucket_count,
+        })
+    }
+
+    //Single bucket without mirroring, which makes HalfKA the plain 768 feature set
+    pub fn single() -> Self {
+        Self::new([0; 64], false).unwrap()
+    }
+
+    //One bucket per king square, 32 of them when mirrored
+    pub fn per_square(mirrored: bool) -> Self {
+        let mut layout = [0; 64];
+        for (square_index, bucket) in layout.iter_mut().enumerate() {
+            *bucket = if mirrored {
+                (square_index / 8 * 4 + (square_index % 8).min(7 - square_index % 8)) as u8
+            } else {
+                square_index as u8
+            };
+        }
+        Self::new(layout, mirrored).unwrap()
+    }
+
+    #[inline]
+    pub fn bucket_count(&self) -> usize {
+        self.bucket_count
+    }
+
+    #[inline]
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    //Whether the board is mirrored horizontally for a perspective with this relative king square
+    #[inline]
+    pub fn mirrors(&self, relative_king_square: Square) -> bool {
+        self.mirrored && relative_king_square.get_file() >= 4
+    }
+
+    #[inline]
+    pub fn bucket(&self, relative_king_square: Square) -> usize {
+        let square = if self.mirrors(relative_king_square) {
+            relative_king_square.get_raw() ^ 7
+        } else {
+            relative_king_square.get_raw()
+        };
+        self.layout[square as usize] as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KingBuckets;
+
+    #[test]
+    fn rejects_unused_buckets() {
+        assert!(KingBuckets::new([2; 64], false).is_err());
+
+        //Files e-h are ignored when mirrored, so bucket 0 is never used
+        let mut layout = [0; 64];
+        for (square_index, bucket) in layout.iter_mut().enumerate() {
+            *bucket = u8::from(square_index % 8 < 4);
+        }
+        assert!(KingBuckets::new(layout, true).is_err());
+        assert_eq!(KingBuckets::new(layout, false).unwrap().bucket_count(), 2);
+    }
+}
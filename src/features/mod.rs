@@ -0,0 +1,5 @@
+mod feature_set;
+mod king_buckets;
+
+pub use feature_set::FeatureSet;
+pub use king_buckets::KingBuckets;
@@ -0,0 +1,5 @@
+mod opening_generator;
+mod random_position;
+
+pub use opening_generator::{MoveSelection, OpeningGenerator, OpeningGeneratorOptions};
+pub use random_position::{random_position, random_position_with_options, RandomPositionOptions};
@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use crate::{ChessBoard, Move, Random, Square, FEN};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoveSelection {
+    //Every legal move is equally likely
+    Uniform,
+    //Quiet moves only, captures are played when there is nothing else
+    AvoidCaptures,
+}
+
+#[derive(Clone)]
+pub struct OpeningGeneratorOptions {
+    //Random plies played from the start position
+    pub plies: usize,
+    pub selection: MoveSelection,
+    //Positions with fewer legal moves are rejected
+    pub min_legal_moves: usize,
+    pub seed: u64,
+    //Rejected or repeated walks in a row before the generator gives up
+    pub max_attempts: usize,
+}
+
+impl Default for OpeningGeneratorOptions {
+    fn default() -> Self {
+        Self {
+            plies: 8,
+            selection: MoveSelection::Uniform,
+            min_legal_moves: 1,
+            seed: 0,
+            max_attempts: 10000,
+        }
+    }
+}
+
+//Random walks from a start position. Walks that reach a terminal position, end with too few
+//legal moves or repeat an earlier position (by zobrist key) are thrown away, so every yielded
+//position is new. The same start position and options always give the same positions.
+pub struct OpeningGenerator {
+    start: ChessBoard,
+    options: OpeningGeneratorOptions,
+    random: Random,
+    seen: HashSet<u64>,
+}
+
+impl OpeningGenerator {
+    pub fn new(start: &FEN, options: OpeningGeneratorOptions) -> Self {
+        Self {
+            start: ChessBoard::from_fen(start),
+            random: Random::from_seed(options.seed),
+            options,
+            seen: HashSet::new(),
+        }
+    }
+
+    //Next unique position, None once max_attempts walks in a row were rejected
+    pub fn next_position(&mut self) -> Option<ChessBoard> {
+        for _ in 0..self.options.max_attempts.max(1) {
+            let Some(board) = self.random_walk() else {
+                continue;
+            };
+            if self.seen.insert(board.get_key().get_raw()) {
+                return Some(board);
+            }
+        }
+        None
+    }
+
+    #[inline]
+    pub fn generated_count(&self) -> usize {
+        self.seen.len()
+    }
+
+    fn random_walk(&mut self) -> Option<ChessBoard> {
+        let mut board = self.start;
+        let mut moves = Vec::with_capacity(64);
+        for _ in 0..self.options.plies {
+            legal_moves(&board, &mut moves);
+            if is_terminal(&board, &moves) {
+                return None;
+            }
+
+            if self.options.selection == MoveSelection::AvoidCaptures
+                && moves.iter().any(|mv| !mv.is_capture())
+            {
+                moves.retain(|mv| !mv.is_capture());
+            }
+            board.apply_move(moves[self.random.next_range(moves.len() as u64) as usize]);
+        }
+
+        legal_moves(&board, &mut moves);
+        if is_terminal(&board, &moves) || moves.len() < self.options.min_legal_moves {
+            return None;
+        }
+
+        //Double pushes always set the en passant square, which would make otherwise equal
+        //positions differ in key and FEN
+        if !moves.iter().any(|mv| mv.is_en_passant()) {
+            board.set_en_passant_square(Square::NULL);
+        }
+        Some(board)
+    }
+}
+
+impl Iterator for OpeningGenerator {
+    type Item = ChessBoard;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_position()
+    }
+}
+
+fn legal_moves(board: &ChessBoard, moves: &mut Vec<Move>) {
+    moves.clear();
+    board.map_legal_moves(|mv| moves.push(mv));
+}
+
+//Checkmate, stalemate, fifty move rule or dead position
+fn is_terminal(board: &ChessBoard, moves: &[Move]) -> bool {
+    moves.is_empty() || board.half_move_counter() >= 100 || board.is_insufficient_material()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{MoveSelection, OpeningGenerator, OpeningGeneratorOptions};
+    use crate::{ChessBoard, FEN};
+
+    fn fens(options: &OpeningGeneratorOptions, count: usize) -> Vec<String> {
+        OpeningGenerator::new(&FEN::start_position(), options.clone())
+            .take(count)
+            .map(|board| board.get_fen().to_string())
+            .collect()
+    }
+
+    fn piece_count(board: &ChessBoard) -> u32 {
+        board.get_occupancy().pop_count()
+    }
+
+    #[test]
+    fn seed_decides_the_positions() {
+        let options = OpeningGeneratorOptions {
+            seed: 49,
+            ..Default::default()
+        };
+        let other_seed = OpeningGeneratorOptions {
+            seed: 50,
+            ..Default::default()
+        };
+
+        let first = fens(&options, 50);
+        assert_eq!(first.len(), 50);
+        assert!(first == fens(&options, 50));
+        assert!(first != fens(&other_seed, 50));
+    }
+
+    #[test]
+    fn positions_are_unique() {
+        let options = OpeningGeneratorOptions {
+            plies: 4,
+            seed: 1,
+            ..Default::default()
+        };
+        let mut generator = OpeningGenerator::new(&FEN::start_position(), options);
+        let keys: HashSet<u64> = generator
+            .by_ref()
+            .take(500)
+            .map(|board| board.get_key().get_raw())
+            .collect();
+
+        assert_eq!(keys.len(), 500);
+        assert_eq!(generator.generated_count(), 500);
+
+        //Only 20 positions are one ply away from the start position
+        let options = OpeningGeneratorOptions {
+            plies: 1,
+            max_attempts: 1000,
+            ..Default::default()
+        };
+        assert_eq!(
+            OpeningGenerator::new(&FEN::start_position(), options).count(),
+            20
+        );
+    }
+
+    #[test]
+    fn min_legal_moves_rejects_positions() {
+        let options = OpeningGeneratorOptions {
+            seed: 2,
+            ..Default::default()
+        };
+        let demanding = OpeningGeneratorOptions {
+            min_legal_moves: 35,
+            ..options.clone()
+        };
+        let move_count = |board: ChessBoard| board.get_legal_moves().len();
+
+        let unrestricted = OpeningGenerator::new(&FEN::start_position(), options);
+        assert!(unrestricted
+            .take(100)
+            .map(move_count)
+            .any(|count| count < 35));
+        let restricted = OpeningGenerator::new(&FEN::start_position(), demanding);
+        assert!(restricted
+            .take(100)
+            .map(move_count)
+            .all(|count| count >= 35));
+
+        let impossible = OpeningGeneratorOptions {
+            min_legal_moves: 300,
+            max_attempts: 20,
+            ..Default::default()
+        };
+        assert!(OpeningGenerator::new(&FEN::start_position(), impossible)
+            .next_position()
+            .is_none());
+    }
+
+    #[test]
+    fn avoid_captures_only_captures_when_forced() {
+        let kiwipete = FEN::kiwipete_position();
+        let start_count = piece_count(&ChessBoard::from_fen(&kiwipete));
+        let options = |selection| OpeningGeneratorOptions {
+            plies: 1,
+            selection,
+            max_attempts: 1000,
+            ..Default::default()
+        };
+
+        let uniform: Vec<_> = OpeningGenerator::new(&kiwipete, options(MoveSelection::Uniform))
+            .map(|board| piece_count(&board))
+            .collect();
+        assert_eq!(uniform.len(), 48);
+        assert!(uniform.iter().any(|&count| count < start_count));
+
+        let quiet: Vec<_> = OpeningGenerator::new(&kiwipete, options(MoveSelection::AvoidCaptures))
+            .map(|board| piece_count(&board))
+            .collect();
+        assert_eq!(quiet.len(), 40);
+        assert!(quiet.iter().all(|&count| count == start_count));
+
+        //Taking the queen is the only legal move
+        let forced = FEN::from_str("k7/7p/8/8/8/8/1q6/K7 w - - 0 1");
+        let boards: Vec<_> =
+            OpeningGenerator::new(&forced, options(MoveSelection::AvoidCaptures)).collect();
+        assert_eq!(boards.len(), 1);
+        assert_eq!(
+            boards[0].get_fen().to_string(),
+            "k7/7p/8/8/8/8/1K6/8 b - - 0 1"
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use crate::{base_structures::Side, Bitboard, CastleRights, ChessBoard, Piece, Random, Square};
+
+#[derive(Clone)]
+pub struct RandomPositionOptions {
+    //None picks the side to move at random
+    pub side_to_move: Option<Side>,
+    //Each castle right the placement allows is granted with a 50% chance
+    pub castling: bool,
+    //When the opponent has a pawn that could have just made a double push and the side to move
+    //can capture it en passant, the en passant square is set with a 50% chance
+    pub en_passant: bool,
+    //Placements that fail the legality checks before giving up
+    pub max_attempts: usize,
+}
+
+impl Default for RandomPositionOptions {
+    fn default() -> Self {
+        Self {
+            side_to_move: None,
+            castling: true,
+            en_passant: true,
+            max_attempts: 100000,
+        }
+    }
+}
+
+//Legal position with the pieces of a material signature like "KRPvKR" (white before the 'v')
+//placed at random. Both sides need exactly one king and pawns never stand on the back ranks.
+pub fn random_position(material: &str, seed: u64) -> Result<ChessBoard, String> {
+    random_position_with_options(material, seed, &RandomPositionOptions::default())
+}
+
+pub fn random_position_with_options(
+    material: &str,
+    seed: u64,
+    options: &RandomPositionOptions,
+) -> Result<ChessBoard, String> {
+    let pieces = parse_material(material)?;
+    let mut random = Random::from_seed(seed);
+    for _ in 0..options.max_attempts.max(1) {
+        if let Some(board) = try_random_position(&pieces, &mut random, options) {
+            return Ok(board);
+        }
+    }
+
+    Err(format!(
+        "no legal position with material {material} found in {} attempts",
+        options.max_attempts.max(1)
+    ))
+}
+
+//Pieces of the signature, pawns first so they always find a square off the back ranks
+fn parse_material(material: &str) -> Result<Vec<(Piece, Side)>, String> {
+    let Some((white, black)) = material.trim().split_once(['v', 'V']) else {
+        return Err(format!("material {material} has no 'v' between the sides"));
+    };
+
+    let mut pieces = Vec::new();
+    for (side, signature) in [(Side::WHITE, white), (Side::BLACK, black)] {
+        let mut kings = 0;
+        for piece_char in signature.chars() {
+            let piece = match piece_char.to_ascii_uppercase() {
+                'P' => Piece::PAWN,
+                'N' => Piece::KNIGHT,
+                'B' => Piece::BISHOP,
+                'R' => Piece::ROOK,
+                'Q' => Piece::QUEEN,
+                'K' => Piece::KING,
+                _ => {
+                    return Err(format!(
+                        "invalid piece '{piece_char}' in material {material}"
+                    ))
+                }
+            };
+            kings += usize::from(piece == Piece::KING);
+            pieces.push((piece, side));
+        }
+        if kings != 1 {
+            return Err(format!(
+                "{side} needs exactly one king, material {material} has {kings}"
+            ));
+        }
+    }
+
+    let pawns = pieces
+        .iter()
+        .filter(|(piece, _)| *piece == Piece::PAWN)
+        .count();
+    if pawns > 48 || pieces.len() > 64 {
+        return Err(format!("material {material} does not fit on the board"));
+    }
+
+    pieces.sort_by_key(|(piece, _)| *piece != Piece::PAWN);
+    Ok(pieces)
+}
+
+fn try_random_position(
+    pieces: &[(Piece, Side)],
+    random: &mut Random,
+    options: &RandomPositionOptions,
+) -> Option<ChessBoard> {
+    let mut board = ChessBoard::default();
+    for &(piece, side) in pieces {
+        let square = loop {
+            let square = Square::from_raw(random.next_range(64) as u8);
+            let back_rank = square.get_rank() == 0 || square.get_rank() == 7;
+            if board.get_piece_on_square(square) == Piece::NONE
+                && !(piece == Piece::PAWN && back_rank)
+            {
+                break square;
+            }
+        };
+        place_piece(&mut board, side, square, piece);
+    }
+
+    let side_to_move = options
+        .side_to_move
+        .unwrap_or_else(|| Side::from_raw(random.next_range(2) as u8));
+    board.set_side_to_move(side_to_move);
+    board.set_move_counters(0, 1);
+    board.refresh_phase();
+
+    //The side that just moved cannot be in check, and no legal move gives more than two checks
+    //or a double check without a discovered slider
+    let checkers = board.get_checkers();
+    if is_king_attacked(&board, side_to_move.flipped())
+        || checkers.pop_count() > 2
+        || (checkers.multiple_one_bits() && !is_possible_double_check(&board, checkers))
+    {
+        return None;
+    }
+
+    if options.castling {
+        board.set_castle_rights(random_castle_rights(&board, random));
+    }
+    if options.en_passant {
+        let candidates = en_passant_candidates(&board);
+        if !candidates.is_empty() && random.next_range(2) == 0 {
+            board.set_en_passant_square(
+                candidates[random.next_range(candidates.len() as u64) as usize],
+            );
+        }
+    }
+
+    board.check_invariants().ok()?;
+    Some(board)
+}
+
+fn random_castle_rights(board: &ChessBoard, random: &mut Random) -> CastleRights {
+    let rights = [
+        (
+            CastleRights::WHITE_KING,
+            Side::WHITE,
+            Square::E1,
+            Square::H1,
+        ),
+        (
+            CastleRights::WHITE_QUEEN,
+            Side::WHITE,
+            Square::E1,
+            Square::A1,
+        ),
+        (
+            CastleRights::BLACK_KING,
+            Side::BLACK,
+            Square::E8,
+            Square::H8,
+        ),
+        (
+            CastleRights::BLACK_QUEEN,
+            Side::BLACK,
+            Square::E8,
+            Square::A8,
+        ),
+    ];
+
+    let mut result = 0;
+    for (right, side, king_square, rook_square) in rights {
+        if is_piece_of_side(board, king_square, Piece::KING, side)
+            && is_piece_of_side(board, rook_square, Piece::ROOK, side)
+            && random.next_range(2) == 0
+        {
+            result |= right.get_raw();
+        }
+    }
+    CastleRights::from_raw(result)
+}
+
+//En passant squares behind opponent pawns that could have just made a double push: the
+//squares the pawn passed are empty, the position before the push did not leave the side to
+//move in check, and the side to move has a legal en passant capture
+fn en_passant_candidates(board: &ChessBoard) -> Vec<Square> {
+    let side_to_move = board.side_to_move();
+    let pushed_side = side_to_move.flipped();
+    let (pawn_rank, direction): (u8, i8) = if pushed_side == Side::WHITE {
+        (3, -8)
+    } else {
+        (4, 8)
+    };
+
+    let mut result = Vec::new();
+    for file in 0..8 {
+        let pawn_square = Square::from_coords(pawn_rank, file);
+        if !is_piece_of_side(board, pawn_square, Piece::PAWN, pushed_side) {
+            continue;
+        }
+
+        let en_passant_square = Square::from_raw((pawn_square.get_raw() as i8 + direction) as u8);
+        let start_square = Square::from_raw((pawn_square.get_raw() as i8 + 2 * direction) as u8);
+        if board.get_piece_on_square(en_passant_square) != Piece::NONE
+            || board.get_piece_on_square(start_square) != Piece::NONE
+        {
+            continue;
+        }
+
+        let mut before_push = *board;
+        remove_piece(&mut before_push, pushed_side, pawn_square, Piece::PAWN);
+        place_piece(&mut before_push, pushed_side, start_square, Piece::PAWN);
+        if is_king_attacked(&before_push, side_to_move) {
+            continue;
+        }
+
+        let mut with_en_passant = *board;
+        with_en_passant.set_en_passant_square(en_passant_square);
+        let mut capturable = false;
+        with_en_passant.map_legal_moves(|mv| capturable |= mv.is_en_passant());
+        if capturable {
+            result.push(en_passant_square);
+        }
+    }
+    result
+}
+
+//A double check uncovers a slider by moving the other checker off its line. A bishop or rook
+//leaves that line along its other line, which never crosses the king, so two bishops never
+//check together and two rooks only do when the moved one just promoted on the king's rank.
+fn is_possible_double_check(board: &ChessBoard, checkers: Bitboard) -> bool {
+    let first = board.get_piece_on_square(checkers.ls1b_square());
+    let second = board.get_piece_on_square(checkers.exclude(checkers.ls1b_square()).ls1b_square());
+    let is_slider = |piece| piece == Piece::BISHOP || piece == Piece::ROOK || piece == Piece::QUEEN;
+    if !is_slider(first) && !is_slider(second) {
+        return false;
+    }
+
+    if first != second {
+        return true;
+    }
+    if first == Piece::BISHOP {
+        return false;
+    }
+
+    let (king_square, promotion_rank) = if board.side_to_move() == Side::WHITE {
+        (board.get_king_square::<true>(), 0)
+    } else {
+        (board.get_king_square::<false>(), 7)
+    };
+    first == Piece::QUEEN || king_square.get_rank() == promotion_rank
+}
+
+fn is_king_attacked(board: &ChessBoard, side: Side) -> bool {
+    if side == Side::WHITE {
+        board.is_in_check::<true, false>()
+    } else {
+        board.is_in_check::<false, true>()
+    }
+}
+
+fn is_piece_of_side(board: &ChessBoard, square: Square, piece: Piece, side: Side) -> bool {
+    board.get_piece_on_square(square) == piece && board.get_piece_color_on_square(square) == side
+}
+
+fn place_piece(board: &mut ChessBoard, side: Side, square: Square, piece: Piece) {
+    if side == Side::WHITE {
+        board.set_piece_on_square::<true>(square, piece)
+    } else {
+        board.set_piece_on_square::<false>(square, piece)
+    }
+}
+
+fn remove_piece(board: &mut ChessBoard, side: Side, square: Square, piece: Piece) {
+    if side == Side::WHITE {
+        board.remove_piece_on_square::<true>(square, piece)
+    } else {
+        board.remove_piece_on_square::<false>(square, piece)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_possible_double_check, parse_material, random_position, random_position_with_options,
+        RandomPositionOptions,
+    };
+    use crate::{base_structures::Side, ChessBoard, Piece, Square, FEN};
+
+    #[test]
+    fn parse_material_errors() {
+        assert!(parse_material("KRPKR").is_err());
+        assert!(parse_material("KKvK").is_err());
+        assert!(parse_material("KvKQK").is_err());
+        assert!(parse_material("RvK").is_err());
+        assert!(parse_material("KXvK").is_err());
+        assert!(parse_material("K1vK").is_err());
+
+        let pieces = parse_material("krpVkr").unwrap();
+        assert_eq!(pieces.len(), 5);
+        assert!(pieces[0].0 == Piece::PAWN && pieces[0].1 == Side::WHITE);
+    }
+
+    #[test]
+    fn seed_decides_the_position() {
+        let fen = |seed| {
+            random_position("KRPvKR", seed)
+                .unwrap()
+                .get_fen()
+                .to_string()
+        };
+        assert_eq!(fen(50), fen(50));
+        assert_ne!(fen(50), fen(51));
+    }
+
+    #[test]
+    fn generated_positions_pass_invariants() {
+        for material in ["KRPvKR", "KPvK", "KQNNBBvKRRPP"] {
+            for seed in 0..300 {
+                let board = random_position(material, seed).unwrap();
+                assert!(board.check_invariants().is_ok(), "{}", board.get_fen());
+
+                let checkers = board.get_checkers();
+                assert!(checkers.pop_count() <= 2);
+                if checkers.multiple_one_bits() {
+                    assert!(is_possible_double_check(&board, checkers));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn castle_and_en_passant_only_when_consistent() {
+        let mut en_passant_count = 0;
+        let mut castle_count = 0;
+        for seed in 0..3000 {
+            let board = random_position("KRRPPPPvKRRPPPP", seed).unwrap();
+            let en_passant = board.en_passant_square();
+            if en_passant != Square::NULL {
+                en_passant_count += 1;
+                assert!(board.get_legal_moves().iter().any(|mv| mv.is_en_passant()));
+            }
+            if board.castle_rights().get_raw() != 0 {
+                castle_count += 1;
+            }
+        }
+        assert!(en_passant_count > 0);
+        assert!(castle_count > 0);
+
+        let options = RandomPositionOptions {
+            castling: false,
+            en_passant: false,
+            ..Default::default()
+        };
+        for seed in 0..300 {
+            let board = random_position_with_options("KRRPPPPvKRRPPPP", seed, &options).unwrap();
+            assert!(board.en_passant_square() == Square::NULL);
+            assert_eq!(board.castle_rights().get_raw(), 0);
+        }
+    }
+
+    #[test]
+    fn impossible_double_checks() {
+        let double_check = |fen| {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            let checkers = board.get_checkers();
+            assert_eq!(checkers.pop_count(), 2, "{fen}");
+            is_possible_double_check(&board, checkers)
+        };
+
+        //Knight and knight, pawn and pawn, pawn and knight
+        assert!(!double_check("4k3/8/3N1N2/8/8/8/8/4K3 b - - 0 1"));
+        assert!(!double_check("8/8/8/4k3/3P1P2/8/8/4K3 b - - 0 1"));
+        assert!(!double_check("8/8/2N5/4k3/3P4/8/8/4K3 b - - 0 1"));
+        //Two bishops, two rooks away from the back rank
+        assert!(!double_check("8/2B5/8/4k3/8/2B5/8/4K3 b - - 0 1"));
+        assert!(!double_check("8/8/8/R3k3/8/8/8/4R1K1 b - - 0 1"));
+
+        //Discovered checks by a slider
+        assert!(double_check("4k3/8/3N4/8/8/8/8/4R1K1 b - - 0 1"));
+        assert!(double_check("8/8/8/4k3/3P4/8/8/4RK2 b - - 0 1"));
+        assert!(double_check("8/2Q5/8/4k3/8/2B5/8/4K3 b - - 0 1"));
+        //Rook promotion on the king's rank, discovering a rook on the file
+        assert!(double_check("3Rk3/8/8/8/8/8/8/4R1K1 b - - 0 1"));
+        assert!(double_check("4r1k1/8/8/8/8/8/8/3rK3 w - - 0 1"));
+    }
+}
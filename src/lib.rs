@@ -2,13 +2,18 @@ mod attacks;
 mod base_structures;
 mod binpacks;
 mod chess_board;
+mod features;
+mod generators;
 mod move_gen;
 mod perft;
+mod policy;
 mod utils;
 
 #[allow(unused)]
 pub use attacks::Attacks;
 #[allow(unused)]
+pub use attacks::MagicGenerator;
+#[allow(unused)]
 pub use base_structures::Bitboard;
 #[allow(unused)]
 pub use base_structures::CastleRights;
@@ -25,18 +30,66 @@ pub use base_structures::Side;
 #[allow(unused)]
 pub use base_structures::Square;
 #[allow(unused)]
+pub use base_structures::ZobristKey;
+#[allow(unused)]
 pub use base_structures::FEN;
 #[allow(unused)]
-pub use base_structures::ZobristKey;
+pub use base_structures::{Epd, EpdOperation};
 #[allow(unused)]
 pub use binpacks::ChessBoardPacked;
 #[allow(unused)]
 pub use binpacks::PolicyPacked;
 #[allow(unused)]
+pub use binpacks::PolicyRecord;
+#[allow(unused)]
+pub use binpacks::{dedup_packed_files, DatasetStats, DedupOptions, DedupSummary};
+#[allow(unused)]
+pub use binpacks::{
+    read_packed, read_raw_records, write_packed, write_packed_file, write_raw_records,
+    PackedHeader, PackedReader, PackedRecord, PackedWriter, PolicyMoveData,
+};
+#[allow(unused)]
+pub use binpacks::{
+    read_stockfish_binpack, write_stockfish_binpack, BinpackEntry, StockfishBinpackReader,
+    StockfishBinpackWriter,
+};
+#[allow(unused)]
+pub use binpacks::{BulletFormat, ChessBoardRecord, GameResult, MarlinFormat};
+#[allow(unused)]
+pub use binpacks::{DataLoader, DataLoaderOptions};
+#[allow(unused)]
+pub use binpacks::{FilterInput, FilterableRecord, PositionFilter};
+#[allow(unused)]
 pub use chess_board::ChessBoard;
 #[allow(unused)]
 pub use chess_board::ChessPosition;
 #[allow(unused)]
+pub use chess_board::InvariantError;
+#[allow(unused)]
+pub use chess_board::{MoveDelta, PieceSquare};
+#[allow(unused)]
+pub use features::{FeatureSet, KingBuckets};
+#[allow(unused)]
+pub use generators::{random_position, random_position_with_options, RandomPositionOptions};
+#[allow(unused)]
+pub use generators::{MoveSelection, OpeningGenerator, OpeningGeneratorOptions};
+#[allow(unused)]
+pub use move_gen::{MoveGenMismatch, ReferenceMoveGen};
+#[allow(unused)]
 pub use perft::Perft;
 #[allow(unused)]
+pub use perft::PerftStats;
+#[allow(unused)]
+pub use perft::PerftTable;
+#[allow(unused)]
+pub use perft::{
+    PerftDepthResult, PerftSuite, PerftSuiteEntry, PerftSuiteResult, PerftSuiteSummary,
+};
+#[allow(unused)]
+pub use perft::{PerftDiff, UciEngine};
+#[allow(unused)]
+pub use policy::PolicyLayout;
+#[allow(unused)]
+pub use utils::Random;
+#[allow(unused)]
 pub use utils::StringUtils;
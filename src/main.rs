@@ -1,6 +1,171 @@
-use spear::{Perft, FEN};
+use std::{process::ExitCode, time::Instant};
 
-fn main() {
+use spear::{ChessBoard, Perft, PerftSuite, StringUtils, FEN};
+
+const USAGE: &str = "usage:
+  spear perft [--fen FEN] --depth N [--divide] [--no-bulk] [--threads N] [--json]
+  spear bench [--json]
+  spear suite <file.epd> [--max-depth N] [--threads N] [--json]
+
+FEN can also be 'startpos' or 'kiwipete'. Running spear without a command runs the bench.";
+
+#[derive(Default)]
+struct Options {
+    fen: Option<String>,
+    depth: Option<u8>,
+    max_depth: Option<u8>,
+    threads: usize,
+    divide: bool,
+    no_bulk: bool,
+    json: bool,
+    path: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => ("bench", &[][..]),
+    };
+
+    if matches!(command, "help" | "--help" | "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let Some(options) = parse_options(rest) else {
+        return usage_error();
+    };
+
+    match command {
+        "perft" => run_perft(&options),
+        "bench" => run_bench(&options),
+        "suite" => run_suite(&options),
+        _ => usage_error(),
+    }
+}
+
+fn parse_options(args: &[String]) -> Option<Options> {
+    let mut options = Options {
+        threads: 1,
+        ..Default::default()
+    };
+
+    let mut index = 0;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        index += 1;
+        match arg {
+            "--fen" => {
+                //fen can be passed either quoted or as separate arguments
+                let mut parts = Vec::new();
+                while index < args.len() && !args[index].starts_with("--") {
+                    parts.push(args[index].as_str());
+                    index += 1;
+                }
+                options.fen = Some(parts.join(" "));
+            }
+            "--depth" => {
+                options.depth = Some(args.get(index)?.parse().ok()?);
+                index += 1;
+            }
+            "--max-depth" => {
+                options.max_depth = Some(args.get(index)?.parse().ok()?);
+                index += 1;
+            }
+            "--threads" => {
+                options.threads = args.get(index)?.parse().ok()?;
+                index += 1;
+            }
+            "--divide" => options.divide = true,
+            "--no-bulk" => options.no_bulk = true,
+            "--json" => options.json = true,
+            _ if options.path.is_none() && !arg.starts_with("--") => {
+                options.path = Some(arg.to_string())
+            }
+            _ => return None,
+        }
+    }
+
+    Some(options)
+}
+
+fn parse_fen(fen: Option<&str>) -> Option<FEN> {
+    match fen {
+        None | Some("startpos") => Some(FEN::start_position()),
+        Some("kiwipete") => Some(FEN::kiwipete_position()),
+        Some(fen) if FEN::validate_fen(fen) => Some(FEN::from_str(fen)),
+        Some(_) => None,
+    }
+}
+
+fn run_perft(options: &Options) -> ExitCode {
+    let Some(depth) = options.depth else {
+        return usage_error();
+    };
+    let Some(fen) = parse_fen(options.fen.as_deref()) else {
+        eprintln!("invalid fen");
+        return ExitCode::from(2);
+    };
+    if depth == 0 && options.divide {
+        eprintln!("--divide requires a depth of at least 1");
+        return ExitCode::from(2);
+    }
+
+    if !options.json {
+        let threads = options.threads.max(1);
+        match (options.no_bulk, options.divide, threads > 1) {
+            (false, false, false) => Perft::perft::<true, false, true>(&fen, depth),
+            (false, true, false) => Perft::perft::<true, true, true>(&fen, depth),
+            (true, false, false) => Perft::perft::<false, false, true>(&fen, depth),
+            (true, true, false) => Perft::perft::<false, true, true>(&fen, depth),
+            (false, false, true) => {
+                Perft::perft_parallel::<true, false, true>(&fen, depth, threads)
+            }
+            (false, true, true) => Perft::perft_parallel::<true, true, true>(&fen, depth, threads),
+            (true, false, true) => {
+                Perft::perft_parallel::<false, false, true>(&fen, depth, threads)
+            }
+            (true, true, true) => Perft::perft_parallel::<false, true, true>(&fen, depth, threads),
+        };
+        return ExitCode::SUCCESS;
+    }
+
+    let board = ChessBoard::from_fen(&fen);
+    let timer = Instant::now();
+    let divide = if options.no_bulk {
+        Perft::perft_parallel_divide::<false>(&board, depth, options.threads)
+    } else {
+        Perft::perft_parallel_divide::<true>(&board, depth, options.threads)
+    };
+    let duration = timer.elapsed().as_millis();
+    let nodes: u128 = if depth == 0 {
+        1
+    } else {
+        divide.iter().map(|(_, nodes)| nodes).sum()
+    };
+
+    let divide_json = if options.divide {
+        let moves: Vec<String> = divide
+            .iter()
+            .map(|(mv, nodes)| format!("{{\"move\":\"{mv}\",\"nodes\":{nodes}}}"))
+            .collect();
+        format!(",\"divide\":[{}]", moves.join(","))
+    } else {
+        String::new()
+    };
+
+    println!(
+        "{{\"fen\":{},\"depth\":{depth},\"bulk\":{},\"threads\":{},\"nodes\":{nodes},\"time_ms\":{duration},\"nps\":{}{divide_json}}}",
+        StringUtils::json_escape(&fen.to_string()),
+        !options.no_bulk,
+        options.threads.max(1),
+        StringUtils::nodes_per_second(nodes, duration)
+    );
+    ExitCode::SUCCESS
+}
+
+fn run_bench(options: &Options) -> ExitCode {
     let fens = [
         (FEN::start_position(), 7, 6, 3195901860),
         (FEN::kiwipete_position(), 6, 5, 8031647685),
@@ -32,26 +197,36 @@ fn main() {
         ),
     ];
 
-    println!("Bulk:");
+    let mut failed = false;
+    if !options.json {
+        println!("Bulk:");
+    }
     for _ in 0..2 {
         for (index, fen) in fens.clone().into_iter().enumerate() {
             let (result_nodes, result_duration) = Perft::perft::<true, false, false>(&fen.0, fen.1);
-            if result_nodes != fen.3 {
+            let passed = result_nodes == fen.3;
+            failed |= !passed;
+            let nps = StringUtils::nodes_per_second(result_nodes, result_duration);
+            if options.json {
                 println!(
-                    "Position {index} has failed in {result_duration}ms ({}nps)",
-                    result_nodes * 1000 / result_duration
+                    "{{\"mode\":\"bulk\",\"index\":{index},\"depth\":{},\"nodes\":{result_nodes},\"passed\":{passed},\"time_ms\":{result_duration},\"nps\":{nps}}}",
+                    fen.1
                 );
             } else {
                 println!(
-                    "Position {index} has passed in {result_duration}ms ({}nps)",
-                    result_nodes * 1000 / result_duration
+                    "Position {index} has {} in {} ({}n/s)",
+                    if passed { "passed" } else { "failed" },
+                    StringUtils::time_to_string(result_duration),
+                    StringUtils::large_number_to_string(nps)
                 );
             }
         }
     }
 
-    println!("\nNon-bulk:");
-    for fen in &fens {
+    if !options.json {
+        println!("\nNon-bulk:");
+    }
+    for (index, fen) in fens.iter().enumerate() {
         let mut nodes = 0u128;
         let mut duration = 0u128;
 
@@ -62,6 +237,51 @@ fn main() {
             duration += result_duration;
         }
 
-        println!("{}nps", nodes * 1000 / duration);
+        let nps = StringUtils::nodes_per_second(nodes, duration);
+        if options.json {
+            println!(
+                "{{\"mode\":\"non-bulk\",\"index\":{index},\"depth\":{},\"nodes\":{nodes},\"time_ms\":{duration},\"nps\":{nps}}}",
+                fen.2
+            );
+        } else {
+            println!(
+                "Position {index}: {nodes} nodes in {} ({}n/s)",
+                StringUtils::time_to_string(duration),
+                StringUtils::large_number_to_string(nps)
+            );
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn run_suite(options: &Options) -> ExitCode {
+    let Some(path) = options.path.as_deref() else {
+        return usage_error();
+    };
+
+    let suite = match PerftSuite::load(path) {
+        Ok(suite) => suite,
+        Err(error) => {
+            eprintln!("failed to load {path}: {error}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let max_depth = options.max_depth.unwrap_or(u8::MAX);
+    let summary = suite.run_and_print(max_depth, options.threads, options.json);
+    if summary.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
     }
 }
+
+fn usage_error() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
@@ -1,4 +1,4 @@
-use crate::{attacks::Rays, Bitboard, ChessBoard, Move};
+use crate::{attacks::Rays, base_structures::Side, Bitboard, ChessBoard, Move};
 
 pub struct MoveGen;
 impl ChessBoard {
@@ -18,6 +18,24 @@ impl ChessBoard {
         Self::map_moves_internal::<F, true, STM_WHITE, NSTM_WHITE>(self, &mut method)
     }
 
+    #[inline]
+    pub fn map_legal_moves<F: FnMut(Move)>(&self, method: F) {
+        if self.side_to_move() == Side::WHITE {
+            self.map_moves::<F, true, false>(method)
+        } else {
+            self.map_moves::<F, false, true>(method)
+        }
+    }
+
+    #[inline]
+    pub fn map_legal_captures<F: FnMut(Move)>(&self, method: F) {
+        if self.side_to_move() == Side::WHITE {
+            self.map_captures::<F, true, false>(method)
+        } else {
+            self.map_captures::<F, false, true>(method)
+        }
+    }
+
     fn map_moves_internal<
         F: FnMut(Move),
         const CAPTURE_ONLY: bool,
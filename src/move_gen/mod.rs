@@ -1,6 +1,10 @@
 mod generate_moves;
 mod king_moves;
+mod move_gen_fuzz;
 mod pawn_moves;
 mod piece_moves;
+mod reference_moves;
 
 pub use generate_moves::MoveGen;
+pub use move_gen_fuzz::MoveGenMismatch;
+pub use reference_moves::ReferenceMoveGen;
@@ -0,0 +1,105 @@
+use crate::{ChessBoard, Move, Random, FEN};
+
+use super::ReferenceMoveGen;
+
+//Position in which the bitboard generator disagrees with the reference generator.
+//`missing` holds moves only the reference found, `extra` moves only `map_moves` found.
+//`capture_mismatch` is set when `map_captures` is not exactly the capture subset.
+#[derive(Clone)]
+pub struct MoveGenMismatch {
+    pub fen: String,
+    pub missing: Vec<Move>,
+    pub extra: Vec<Move>,
+    pub capture_mismatch: bool,
+}
+
+impl ReferenceMoveGen {
+    //Compares `map_legal_moves` and `map_legal_captures` with the reference generator
+    pub fn compare(board: &ChessBoard) -> Option<MoveGenMismatch> {
+        let mut reference = ReferenceMoveGen::legal_moves(board);
+        let mut moves = board.get_legal_moves();
+        let mut captures = Vec::new();
+        board.map_legal_captures(|mv| captures.push(mv));
+
+        let mut reference_captures: Vec<Move> =
+            reference.iter().copied().filter(Move::is_capture).collect();
+        for list in [
+            &mut reference,
+            &mut moves,
+            &mut captures,
+            &mut reference_captures,
+        ] {
+            list.sort_by_key(Move::get_raw);
+        }
+
+        let missing: Vec<Move> = reference
+            .iter()
+            .copied()
+            .filter(|mv| !moves.contains(mv))
+            .collect();
+        let extra: Vec<Move> = moves
+            .iter()
+            .copied()
+            .filter(|mv| !reference.contains(mv))
+            .collect();
+        let capture_mismatch = captures != reference_captures;
+
+        if missing.is_empty()
+            && extra.is_empty()
+            && !capture_mismatch
+            && moves.len() == reference.len()
+        {
+            return None;
+        }
+
+        Some(MoveGenMismatch {
+            fen: board.get_fen().to_string(),
+            missing,
+            extra,
+            capture_mismatch,
+        })
+    }
+
+    //Plays `games` random games of up to `max_plies` plies from every fen and compares both
+    //generators in every visited position. Returns the number of compared positions.
+    pub fn fuzz(
+        fens: &[FEN],
+        games: usize,
+        max_plies: usize,
+        seed: u64,
+    ) -> Result<u64, MoveGenMismatch> {
+        let mut random = Random::from_seed(seed);
+        let mut positions = 0;
+        for fen in fens {
+            for _ in 0..games {
+                let mut board = ChessBoard::from_fen(fen);
+                for _ in 0..=max_plies {
+                    if let Some(mismatch) = Self::compare(&board) {
+                        return Err(mismatch);
+                    }
+                    positions += 1;
+
+                    let moves = board.get_legal_moves();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    board.apply_move(moves[random.next_range(moves.len() as u64) as usize]);
+                }
+            }
+        }
+
+        Ok(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChessBoard, ReferenceMoveGen, FEN};
+
+    #[test]
+    fn rank_pinned_pawn_cannot_push_promote() {
+        let board = ChessBoard::from_fen(&FEN::from_str("8/K1P4r/8/8/8/8/8/4k3 w - - 0 1"));
+        assert!(board.get_legal_moves().iter().all(|mv| !mv.is_promotion()));
+        assert!(ReferenceMoveGen::compare(&board).is_none());
+    }
+}
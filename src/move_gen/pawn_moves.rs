@@ -99,7 +99,12 @@ fn handle_pawn_pushes<F: FnMut(Move), const STM_WHITE: bool>(
         } else {
             pawn_square.shift_right(8)
         };
-        if push_map.get_bit(to_square) {
+        let promotion_map = if pinned_pawns.get_bit(pawn_square) {
+            push_map & ortographic_pins
+        } else {
+            push_map
+        };
+        if promotion_map.get_bit(to_square) {
             method(Move::from_squares(
                 pawn_square,
                 to_square,
@@ -0,0 +1,310 @@
+use crate::{CastleRights, ChessBoard, Move, MoveFlag, Piece, Side, Square};
+
+//Deliberately simple mailbox move generator used as a reference for the bitboard generator.
+//Every pseudo-legal move is made on a copy of the mailbox and kept only when it does not
+//leave the own king attacked. It shares no attack tables or masks with `MoveGen`.
+pub struct ReferenceMoveGen {
+    squares: [Option<(Piece, Side)>; 64],
+    side_to_move: Side,
+    castle_rights: CastleRights,
+    en_passant: Square,
+}
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+impl ReferenceMoveGen {
+    pub fn from_board(board: &ChessBoard) -> Self {
+        let mut squares = [None; 64];
+        for (index, entry) in squares.iter_mut().enumerate() {
+            let square = Square::from_raw(index as u8);
+            let piece = board.get_piece_on_square(square);
+            if piece != Piece::NONE {
+                *entry = Some((piece, board.get_piece_color_on_square(square)));
+            }
+        }
+
+        Self {
+            squares,
+            side_to_move: board.side_to_move(),
+            castle_rights: board.castle_rights(),
+            en_passant: board.en_passant_square(),
+        }
+    }
+
+    //Returns all legal moves of the position, encoded the same way as `map_moves`
+    pub fn legal_moves(board: &ChessBoard) -> Vec<Move> {
+        let generator = Self::from_board(board);
+        let mut moves = Vec::new();
+        for mv in generator.pseudo_legal_moves() {
+            let mut copy = generator.squares;
+            Self::make_move_on(&mut copy, mv, generator.side_to_move);
+            let king_square =
+                (0..64).find(|&index| copy[index] == Some((Piece::KING, generator.side_to_move)));
+            let safe = king_square.is_none_or(|index| {
+                !Self::is_attacked(&copy, index as i8, generator.side_to_move.flipped())
+            });
+            if safe {
+                moves.push(mv);
+            }
+        }
+        moves
+    }
+
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for index in 0..64 {
+            let Some((piece, side)) = self.squares[index as usize] else {
+                continue;
+            };
+            if side != self.side_to_move {
+                continue;
+            }
+
+            match piece {
+                Piece::PAWN => self.pawn_moves(index, &mut moves),
+                Piece::KNIGHT => self.step_moves(index, &KNIGHT_OFFSETS, &mut moves),
+                Piece::BISHOP => self.slide_moves(index, &BISHOP_DIRECTIONS, &mut moves),
+                Piece::ROOK => self.slide_moves(index, &ROOK_DIRECTIONS, &mut moves),
+                Piece::QUEEN => {
+                    self.slide_moves(index, &BISHOP_DIRECTIONS, &mut moves);
+                    self.slide_moves(index, &ROOK_DIRECTIONS, &mut moves);
+                }
+                _ => {
+                    self.step_moves(index, &KING_OFFSETS, &mut moves);
+                    self.castle_moves(index, &mut moves);
+                }
+            }
+        }
+        moves
+    }
+
+    fn pawn_moves(&self, index: i8, moves: &mut Vec<Move>) {
+        let white = self.side_to_move == Side::WHITE;
+        let forward = if white { 1 } else { -1 };
+        let start_rank = if white { 1 } else { 6 };
+        let promotion_rank = if white { 7 } else { 0 };
+        let (file, rank) = (index % 8, index / 8);
+
+        let push = |to: i8, flag: u16, moves: &mut Vec<Move>| {
+            if to / 8 == promotion_rank {
+                for promotion in MoveFlag::KNIGHT_PROMOTION..=MoveFlag::QUEEN_PROMOTION {
+                    moves.push(create_move(index, to, promotion | flag));
+                }
+            } else {
+                moves.push(create_move(index, to, flag));
+            }
+        };
+
+        let single = index + 8 * forward;
+        if self.squares[single as usize].is_none() {
+            push(single, MoveFlag::QUIET_MOVE, moves);
+            let double = single + 8 * forward;
+            if rank == start_rank && self.squares[double as usize].is_none() {
+                moves.push(create_move(index, double, MoveFlag::DOUBLE_PUSH));
+            }
+        }
+
+        for file_offset in [-1, 1] {
+            let Some(target) = offset_square(file, rank, file_offset, forward) else {
+                continue;
+            };
+            match self.squares[target as usize] {
+                Some((_, side)) if side != self.side_to_move => {
+                    push(target, MoveFlag::CAPTURE, moves)
+                }
+                None if target as u8 == self.en_passant.get_raw() => {
+                    moves.push(create_move(index, target, MoveFlag::EN_PASSANT))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn step_moves(&self, index: i8, offsets: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(file_offset, rank_offset) in offsets {
+            if let Some(target) = offset_square(index % 8, index / 8, file_offset, rank_offset) {
+                self.push_target(index, target, moves);
+            }
+        }
+    }
+
+    fn slide_moves(&self, index: i8, directions: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(file_offset, rank_offset) in directions {
+            let mut current = index;
+            while let Some(target) =
+                offset_square(current % 8, current / 8, file_offset, rank_offset)
+            {
+                self.push_target(index, target, moves);
+                if self.squares[target as usize].is_some() {
+                    break;
+                }
+                current = target;
+            }
+        }
+    }
+
+    fn push_target(&self, from: i8, to: i8, moves: &mut Vec<Move>) {
+        match self.squares[to as usize] {
+            None => moves.push(create_move(from, to, MoveFlag::QUIET_MOVE)),
+            Some((_, side)) if side != self.side_to_move => {
+                moves.push(create_move(from, to, MoveFlag::CAPTURE))
+            }
+            _ => {}
+        }
+    }
+
+    fn castle_moves(&self, index: i8, moves: &mut Vec<Move>) {
+        let (king_right, queen_right, home) = if self.side_to_move == Side::WHITE {
+            (CastleRights::WHITE_KING, CastleRights::WHITE_QUEEN, 4)
+        } else {
+            (CastleRights::BLACK_KING, CastleRights::BLACK_QUEEN, 60)
+        };
+        if index != home {
+            return;
+        }
+
+        let enemy = self.side_to_move.flipped();
+        let rook = Some((Piece::ROOK, self.side_to_move));
+        let empty = |squares: &[i8]| {
+            squares
+                .iter()
+                .all(|&sq| self.squares[sq as usize].is_none())
+        };
+        let safe = |squares: &[i8]| {
+            squares
+                .iter()
+                .all(|&sq| !Self::is_attacked(&self.squares, sq, enemy))
+        };
+
+        if self.castle_rights.has_right(king_right)
+            && self.squares[(home + 3) as usize] == rook
+            && empty(&[home + 1, home + 2])
+            && safe(&[home, home + 1, home + 2])
+        {
+            moves.push(create_move(home, home + 2, MoveFlag::KING_SIDE_CASTLE));
+        }
+
+        if self.castle_rights.has_right(queen_right)
+            && self.squares[(home - 4) as usize] == rook
+            && empty(&[home - 1, home - 2, home - 3])
+            && safe(&[home, home - 1, home - 2])
+        {
+            moves.push(create_move(home, home - 2, MoveFlag::QUEEN_SIDE_CASTLE));
+        }
+    }
+
+    fn make_move_on(squares: &mut [Option<(Piece, Side)>; 64], mv: Move, side: Side) {
+        let from = mv.get_from_square().get_raw() as usize;
+        let to = mv.get_to_square().get_raw() as usize;
+        let moved = squares[from].take();
+
+        match mv.get_flag() {
+            MoveFlag::EN_PASSANT => {
+                let captured = if side == Side::WHITE { to - 8 } else { to + 8 };
+                squares[captured] = None;
+            }
+            MoveFlag::KING_SIDE_CASTLE => squares[to - 1] = squares[to + 1].take(),
+            MoveFlag::QUEEN_SIDE_CASTLE => squares[to + 1] = squares[to - 2].take(),
+            _ => {}
+        }
+
+        squares[to] = if mv.is_promotion() {
+            Some((mv.get_promotion_piece(), side))
+        } else {
+            moved
+        };
+    }
+
+    fn is_attacked(squares: &[Option<(Piece, Side)>; 64], index: i8, attacker: Side) -> bool {
+        let (file, rank) = (index % 8, index / 8);
+        let holds = |target: Option<i8>, pieces: &[Piece]| {
+            target.is_some_and(|target| {
+                squares[target as usize]
+                    .is_some_and(|(piece, side)| side == attacker && pieces.contains(&piece))
+            })
+        };
+
+        //pawns attacking this square stand one rank behind it from the attacker's point of view
+        let pawn_rank = if attacker == Side::WHITE { -1 } else { 1 };
+        if holds(offset_square(file, rank, -1, pawn_rank), &[Piece::PAWN])
+            || holds(offset_square(file, rank, 1, pawn_rank), &[Piece::PAWN])
+        {
+            return true;
+        }
+
+        for &(file_offset, rank_offset) in &KNIGHT_OFFSETS {
+            if holds(
+                offset_square(file, rank, file_offset, rank_offset),
+                &[Piece::KNIGHT],
+            ) {
+                return true;
+            }
+        }
+
+        for &(file_offset, rank_offset) in &KING_OFFSETS {
+            if holds(
+                offset_square(file, rank, file_offset, rank_offset),
+                &[Piece::KING],
+            ) {
+                return true;
+            }
+        }
+
+        let sliders = [
+            (&BISHOP_DIRECTIONS, [Piece::BISHOP, Piece::QUEEN]),
+            (&ROOK_DIRECTIONS, [Piece::ROOK, Piece::QUEEN]),
+        ];
+        for (directions, pieces) in sliders {
+            for &(file_offset, rank_offset) in directions {
+                let mut current = index;
+                while let Some(target) =
+                    offset_square(current % 8, current / 8, file_offset, rank_offset)
+                {
+                    if squares[target as usize].is_some() {
+                        if holds(Some(target), &pieces) {
+                            return true;
+                        }
+                        break;
+                    }
+                    current = target;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+fn offset_square(file: i8, rank: i8, file_offset: i8, rank_offset: i8) -> Option<i8> {
+    let (file, rank) = (file + file_offset, rank + rank_offset);
+    ((0..8).contains(&file) && (0..8).contains(&rank)).then_some(rank * 8 + file)
+}
+
+fn create_move(from: i8, to: i8, flag: u16) -> Move {
+    Move::from_squares(
+        Square::from_raw(from as u8),
+        Square::from_raw(to as u8),
+        flag,
+    )
+}
@@ -1,3 +1,14 @@
+mod perft_diff;
+mod perft_hashed;
+mod perft_parallel;
+mod perft_stats;
+mod perft_suite;
 mod perft_tests;
 
+pub use perft_diff::{PerftDiff, UciEngine};
+pub use perft_hashed::PerftTable;
+pub use perft_stats::PerftStats;
+pub use perft_suite::{
+    PerftDepthResult, PerftSuite, PerftSuiteEntry, PerftSuiteResult, PerftSuiteSummary,
+};
 pub use perft_tests::Perft;
@@ -0,0 +1,311 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use crate::{ChessBoard, Move, Perft};
+
+//UCI engine subprocess used as a perft reference. Only `position fen` and `go perft` are
+//needed, so any program or script answering `go perft <depth>` with `<move>: <nodes>` lines
+//followed by a `Nodes searched` line works.
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+//Position in which the move lists of Spear and the reference engine differ.
+//`moves` leads from the initial position to `fen`.
+#[derive(Clone, Default)]
+pub struct PerftDiff {
+    pub fen: String,
+    pub moves: Vec<String>,
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+impl UciEngine {
+    pub fn launch(command: &str, args: &[String]) -> io::Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or(io::ErrorKind::BrokenPipe)?;
+        let stdout = BufReader::new(child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?);
+        let mut engine = Self {
+            child,
+            stdin,
+            stdout,
+        };
+
+        engine.send("uci")?;
+        engine.wait_for("uciok")?;
+        engine.send("isready")?;
+        engine.wait_for("readyok")?;
+        Ok(engine)
+    }
+
+    pub fn perft_divide(&mut self, fen: &str, depth: u8) -> io::Result<Vec<(String, u128)>> {
+        self.send(&format!("position fen {fen}"))?;
+        self.send(&format!("go perft {depth}"))?;
+
+        let mut result = Vec::new();
+        loop {
+            let line = self.read_line()?;
+            let line = line.trim();
+            if line.to_ascii_lowercase().starts_with("nodes") {
+                return Ok(result);
+            }
+
+            if let Some(entry) = parse_divide_line(line) {
+                result.push(entry);
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "engine closed its output",
+            ));
+        }
+        Ok(line)
+    }
+
+    fn wait_for(&mut self, token: &str) -> io::Result<()> {
+        while self.read_line()?.trim() != token {}
+        Ok(())
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        self.send("quit").ok();
+        if self.child.try_wait().ok().flatten().is_none() {
+            self.child.kill().ok();
+        }
+        self.child.wait().ok();
+    }
+}
+
+impl Perft {
+    //Compares divide results with the engine and descends into the first move with a different
+    //node count until the move lists themselves differ. Returns None when both agree.
+    pub fn find_divide_mismatch(
+        engine: &mut UciEngine,
+        board: &ChessBoard,
+        depth: u8,
+    ) -> io::Result<Option<PerftDiff>> {
+        let mut moves = Vec::new();
+        find_divide_mismatch_internal(engine, board, depth, &mut moves)
+    }
+}
+
+fn find_divide_mismatch_internal(
+    engine: &mut UciEngine,
+    board: &ChessBoard,
+    depth: u8,
+    moves: &mut Vec<String>,
+) -> io::Result<Option<PerftDiff>> {
+    if depth == 0 {
+        return Ok(None);
+    }
+
+    let fen = board.get_fen().to_string();
+    let spear_results: Vec<(Move, u128)> = Perft::perft_parallel_divide::<true>(board, depth, 1);
+    let engine_results: HashMap<String, u128> =
+        engine.perft_divide(&fen, depth)?.into_iter().collect();
+
+    let spear_moves: HashMap<String, u128> = spear_results
+        .iter()
+        .map(|(mv, nodes)| (mv.to_string(), *nodes))
+        .collect();
+    let mut missing: Vec<String> = engine_results
+        .keys()
+        .filter(|mv| !spear_moves.contains_key(*mv))
+        .cloned()
+        .collect();
+    let mut extra: Vec<String> = spear_moves
+        .keys()
+        .filter(|mv| !engine_results.contains_key(*mv))
+        .cloned()
+        .collect();
+
+    if !missing.is_empty() || !extra.is_empty() {
+        missing.sort();
+        extra.sort();
+        return Ok(Some(PerftDiff {
+            fen,
+            moves: moves.clone(),
+            missing,
+            extra,
+        }));
+    }
+
+    for (mv, nodes) in spear_results {
+        if engine_results[&mv.to_string()] == nodes {
+            continue;
+        }
+
+        let mut board_copy = *board;
+        board_copy.apply_move(mv);
+        moves.push(mv.to_string());
+        return find_divide_mismatch_internal(engine, &board_copy, depth - 1, moves);
+    }
+
+    Ok(None)
+}
+
+fn parse_divide_line(line: &str) -> Option<(String, u128)> {
+    let (mv, nodes) = line.split_once(':').or_else(|| line.split_once(" - "))?;
+    let mv = mv.trim();
+    let valid_move = (4..=5).contains(&mv.len())
+        && mv.is_ascii()
+        && mv.as_bytes()[0].is_ascii_lowercase()
+        && mv.as_bytes()[1].is_ascii_digit();
+    if !valid_move {
+        return None;
+    }
+
+    Some((mv.to_string(), nodes.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{parse_divide_line, UciEngine};
+    use crate::{ChessBoard, Perft, FEN};
+
+    //Stand-in engine answering every `go perft` with the next divide_<n>.txt of its directory
+    fn scripted_engine(dir: &Path, divides: &[String]) -> UciEngine {
+        fs::create_dir_all(dir).unwrap();
+        for (index, divide) in divides.iter().enumerate() {
+            fs::write(dir.join(format!("divide_{}.txt", index + 1)), divide).unwrap();
+        }
+
+        let script = format!(
+            "n=0\n\
+             while read -r line; do\n\
+             case \"$line\" in\n\
+             uci) echo 'id name scripted'; echo uciok ;;\n\
+             isready) echo readyok ;;\n\
+             'go perft'*) n=$((n + 1)); cat '{}/divide_'$n.txt ;;\n\
+             quit) exit 0 ;;\n\
+             esac\n\
+             done\n",
+            dir.display()
+        );
+        let script_path = dir.join("engine.sh");
+        fs::write(&script_path, script).unwrap();
+        UciEngine::launch("sh", &[script_path.display().to_string()]).unwrap()
+    }
+
+    //Divide output of Spear with the node counts adjusted, moves dropped and moves added
+    fn divide(
+        board: &ChessBoard,
+        depth: u8,
+        adjust: (&str, i64),
+        drop: &[&str],
+        add: &[&str],
+        separator: &str,
+    ) -> String {
+        let mut output = String::from("info string scripted divide\n");
+        let mut total = 0;
+        for (mv, nodes) in Perft::perft_parallel_divide::<true>(board, depth, 1) {
+            let mv = mv.to_string();
+            if drop.contains(&mv.as_str()) {
+                continue;
+            }
+
+            let nodes = if mv == adjust.0 {
+                nodes.checked_add_signed(adjust.1 as i128).unwrap()
+            } else {
+                nodes
+            };
+            total += nodes;
+            output.push_str(&format!("{mv}{separator}{nodes}\n"));
+        }
+        for mv in add {
+            total += 1;
+            output.push_str(&format!("{mv}{separator}1\n"));
+        }
+        output.push_str(&format!("\nNodes searched: {total}\n"));
+        output
+    }
+
+    fn play(board: &ChessBoard, uci: &str) -> ChessBoard {
+        let mut result = *board;
+        result.apply_move(board.parse_uci_move(uci).unwrap());
+        result
+    }
+
+    #[test]
+    fn descends_into_the_first_mismatch() {
+        let dir = std::env::temp_dir().join(format!("spear-perft-diff-{}", std::process::id()));
+        let root = ChessBoard::from_fen(&FEN::start_position());
+        let child = play(&root, "g1f3");
+        let grandchild = play(&child, "b8c6");
+        let divides = [
+            divide(&root, 3, ("g1f3", 1), &[], &[], ": "),
+            divide(&child, 2, ("b8c6", -1), &[], &[], " - "),
+            divide(&grandchild, 1, ("", 0), &["a2a3"], &["e1e2"], ": "),
+        ];
+
+        let mut engine = scripted_engine(&dir, &divides);
+        let diff = Perft::find_divide_mismatch(&mut engine, &root, 3)
+            .unwrap()
+            .unwrap();
+        drop(engine);
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(diff.fen, grandchild.get_fen().to_string());
+        assert_eq!(diff.moves, ["g1f3", "b8c6"]);
+        assert_eq!(diff.missing, ["e1e2"]);
+        assert_eq!(diff.extra, ["a2a3"]);
+    }
+
+    #[test]
+    fn agreeing_engine_has_no_mismatch() {
+        let dir = std::env::temp_dir().join(format!("spear-perft-agree-{}", std::process::id()));
+        let board = ChessBoard::from_fen(&FEN::kiwipete_position());
+        let divides = [divide(&board, 2, ("", 0), &[], &[], ": ")];
+
+        let mut engine = scripted_engine(&dir, &divides);
+        let diff = Perft::find_divide_mismatch(&mut engine, &board, 2).unwrap();
+        drop(engine);
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(diff.is_none());
+    }
+
+    #[test]
+    fn divide_lines() {
+        assert_eq!(
+            parse_divide_line("e2e4: 20"),
+            Some(("e2e4".to_string(), 20))
+        );
+        assert_eq!(
+            parse_divide_line("e7e8q - 5"),
+            Some(("e7e8q".to_string(), 5))
+        );
+        assert_eq!(
+            parse_divide_line(" a2a3:  380 "),
+            Some(("a2a3".to_string(), 380))
+        );
+        assert_eq!(parse_divide_line("Nodes searched: 400"), None);
+        assert_eq!(parse_divide_line("info string depth: 2"), None);
+        assert_eq!(parse_divide_line("e2e4: many"), None);
+        assert_eq!(parse_divide_line(""), None);
+    }
+}
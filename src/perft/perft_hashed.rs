@@ -0,0 +1,209 @@
+use std::time::Instant;
+
+use crate::{base_structures::Side, ChessBoard, Perft, FEN};
+
+use super::perft_tests::{perft_board, print_perft_footer, print_perft_header};
+
+//Transposition table for perft node counts. Every entry stores the full zobrist key next to the
+//depth and the node count, so a hit is only accepted when the complete key matches.
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct PerftEntry {
+    key: u64,
+    //node count in the upper 56 bits, depth in the lower 8 bits
+    data: u64,
+}
+
+impl PerftTable {
+    pub fn new(size_mb: usize) -> Self {
+        let entry_count = (size_mb * 1024 * 1024 / std::mem::size_of::<PerftEntry>()).max(1);
+        Self {
+            entries: vec![PerftEntry::default(); entry_count],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(PerftEntry::default())
+    }
+
+    #[inline]
+    fn index(&self, key: u64) -> usize {
+        ((key as u128 * self.entries.len() as u128) >> 64) as usize
+    }
+
+    #[inline]
+    fn probe(&self, key: u64, depth: u8) -> Option<u128> {
+        let entry = self.entries[self.index(key)];
+        if entry.key == key && entry.data & 0xFF == depth as u64 && entry.data != 0 {
+            Some((entry.data >> 8) as u128)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn store(&mut self, key: u64, depth: u8, node_count: u128) {
+        if node_count >= 1 << 56 {
+            return;
+        }
+
+        let index = self.index(key);
+        self.entries[index] = PerftEntry {
+            key,
+            data: (node_count as u64) << 8 | depth as u64,
+        };
+    }
+}
+
+impl Perft {
+    pub fn perft_hashed<const BULK: bool, const SPLIT: bool, const PRINT: bool>(
+        fen: &FEN,
+        depth: u8,
+        table: &mut PerftTable,
+    ) -> (u128, u128) {
+        let board = ChessBoard::from_fen(fen);
+
+        if PRINT {
+            print_perft_header(&board, depth, SPLIT, BULK, 1);
+            println!("  Hash entries: {}", table.entries.len());
+            println!("-----------------------------------------------------------");
+        }
+
+        let timer = Instant::now();
+        let result = if depth == 0 {
+            1
+        } else if board.side_to_move() == Side::WHITE {
+            perft_hashed_internal::<BULK, SPLIT, PRINT, true, true, false>(&board, depth, table)
+        } else {
+            perft_hashed_internal::<BULK, SPLIT, PRINT, true, false, true>(&board, depth, table)
+        };
+        let duration = timer.elapsed().as_millis();
+
+        if PRINT {
+            print_perft_footer(result, duration);
+        }
+
+        (result, duration)
+    }
+
+    //Compares hashed and plain perft at every depth up to max_depth, returns the first depth at
+    //which they disagree together with both counts (hashed, plain).
+    pub fn verify_hashed(
+        fen: &FEN,
+        max_depth: u8,
+        table: &mut PerftTable,
+    ) -> Option<(u8, u128, u128)> {
+        let board = ChessBoard::from_fen(fen);
+        for depth in 1..=max_depth {
+            table.clear();
+            let (hashed, _) = Self::perft_hashed::<true, false, false>(fen, depth, table);
+            let plain = perft_board::<true>(&board, depth);
+            if hashed != plain {
+                return Some((depth, hashed, plain));
+            }
+        }
+
+        None
+    }
+}
+
+fn perft_hashed_internal<
+    const BULK: bool,
+    const SPLIT: bool,
+    const PRINT: bool,
+    const FIRST: bool,
+    const STM_WHITE: bool,
+    const NSTM_WHITE: bool,
+>(
+    board: &ChessBoard,
+    depth: u8,
+    table: &mut PerftTable,
+) -> u128 {
+    let mut node_count = 0u128;
+
+    if BULK && depth == 1 {
+        board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|_| {
+            node_count += 1;
+        });
+        return node_count;
+    }
+
+    if !BULK && depth == 0 {
+        return 1;
+    }
+
+    let key = board.get_key().get_raw();
+    if !FIRST {
+        if let Some(result) = table.probe(key, depth) {
+            return result;
+        }
+    }
+
+    board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| {
+        let mut board_copy = *board;
+        board_copy.make_move::<STM_WHITE, NSTM_WHITE>(mv);
+        let result = perft_hashed_internal::<BULK, SPLIT, PRINT, false, NSTM_WHITE, STM_WHITE>(
+            &board_copy,
+            depth - 1,
+            table,
+        );
+        node_count += result;
+
+        if SPLIT && PRINT && FIRST {
+            println!("{mv} - {result}")
+        }
+    });
+
+    table.store(key, depth, node_count);
+    node_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PerftTable;
+    use crate::{Perft, FEN};
+
+    const POSITIONS: [(&str, u8, u128); 3] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            4,
+            197281,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+            97862,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+    ];
+
+    #[test]
+    fn hashed_perft_matches_known_counts() {
+        //A single entry replaces on every store and mostly holds other depths
+        for size_mb in [0, 1] {
+            let mut table = PerftTable::new(size_mb);
+            for (fen, depth, expected) in POSITIONS {
+                let fen = FEN::from_str(fen);
+                table.clear();
+                let (bulk, _) = Perft::perft_hashed::<true, false, false>(&fen, depth, &mut table);
+                table.clear();
+                let (plain, _) =
+                    Perft::perft_hashed::<false, false, false>(&fen, depth, &mut table);
+                assert_eq!(bulk, expected);
+                assert_eq!(plain, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_hashed_agrees_with_plain_perft() {
+        let mut table = PerftTable::new(0);
+        for (fen, depth, _) in POSITIONS {
+            let result = Perft::verify_hashed(&FEN::from_str(fen), depth - 1, &mut table);
+            assert_eq!(result, None);
+        }
+    }
+}
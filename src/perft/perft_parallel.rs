@@ -0,0 +1,203 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Instant,
+};
+
+use crate::{ChessBoard, Move, Perft, FEN};
+
+use super::perft_tests::{perft_board, print_perft_footer, print_perft_header};
+
+//Root moves are split into subtrees that are pulled from a shared queue by the worker threads.
+//When the root has too few moves to keep every thread busy the split goes one ply deeper.
+const TASKS_PER_THREAD: usize = 4;
+
+struct PerftTask {
+    root_index: usize,
+    board: ChessBoard,
+    depth: u8,
+}
+
+impl Perft {
+    pub fn perft_parallel<const BULK: bool, const SPLIT: bool, const PRINT: bool>(
+        fen: &FEN,
+        depth: u8,
+        threads: usize,
+    ) -> (u128, u128) {
+        let board = ChessBoard::from_fen(fen);
+        let threads = threads.max(1);
+
+        if PRINT {
+            print_perft_header(&board, depth, SPLIT, BULK, threads);
+        }
+
+        let timer = Instant::now();
+        let (root_moves, root_results) = perft_parallel_root::<BULK>(&board, depth, threads);
+        let result = if depth == 0 {
+            1
+        } else {
+            root_results.iter().sum()
+        };
+        let duration = timer.elapsed().as_millis();
+
+        if SPLIT && PRINT {
+            for (mv, result) in root_moves.iter().zip(&root_results) {
+                println!("{mv} - {result}")
+            }
+        }
+
+        if PRINT {
+            print_perft_footer(result, duration);
+        }
+
+        (result, duration)
+    }
+
+    //Returns node counts of every root move in move generation order
+    pub fn perft_parallel_divide<const BULK: bool>(
+        board: &ChessBoard,
+        depth: u8,
+        threads: usize,
+    ) -> Vec<(Move, u128)> {
+        let (root_moves, root_results) = perft_parallel_root::<BULK>(board, depth, threads.max(1));
+        root_moves.into_iter().zip(root_results).collect()
+    }
+}
+
+fn perft_parallel_root<const BULK: bool>(
+    board: &ChessBoard,
+    depth: u8,
+    threads: usize,
+) -> (Vec<Move>, Vec<u128>) {
+    if depth == 0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut root_moves = Vec::new();
+    board.map_legal_moves(|mv| root_moves.push(mv));
+
+    let split_deeper = depth >= 3 && root_moves.len() < threads * TASKS_PER_THREAD;
+    let mut tasks = Vec::new();
+    for (root_index, &mv) in root_moves.iter().enumerate() {
+        let mut board_copy = *board;
+        board_copy.apply_move(mv);
+
+        if !split_deeper {
+            tasks.push(PerftTask {
+                root_index,
+                board: board_copy,
+                depth: depth - 1,
+            });
+            continue;
+        }
+
+        board_copy.map_legal_moves(|child_mv| {
+            let mut child_board = board_copy;
+            child_board.apply_move(child_mv);
+            tasks.push(PerftTask {
+                root_index,
+                board: child_board,
+                depth: depth - 2,
+            });
+        });
+    }
+
+    let next_task = AtomicUsize::new(0);
+    let mut root_results = vec![0u128; root_moves.len()];
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.min(tasks.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut partial_results = Vec::new();
+                    loop {
+                        let task_index = next_task.fetch_add(1, Ordering::Relaxed);
+                        let Some(task) = tasks.get(task_index) else {
+                            break;
+                        };
+                        partial_results.push((
+                            task.root_index,
+                            perft_board::<BULK>(&task.board, task.depth),
+                        ));
+                    }
+                    partial_results
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            for (root_index, result) in handle.join().unwrap() {
+                root_results[root_index] += result;
+            }
+        }
+    });
+
+    (root_moves, root_results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChessBoard, Perft, FEN};
+
+    //(fen, depth, threads), the narrow roots with many threads split on the second ply
+    const CASES: [(&str, u8, usize); 6] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            3,
+            1,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            2,
+            8,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+            4,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 8),
+        ("4k3/8/8/8/8/8/2n5/R3K3 w - - 0 1", 4, 16),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            3,
+            3,
+        ),
+    ];
+
+    #[test]
+    fn parallel_perft_matches_single_threaded() {
+        for (fen, depth, threads) in CASES {
+            let fen = FEN::from_str(fen);
+            let (expected, _) = Perft::perft::<true, false, false>(&fen, depth);
+            let (bulk, _) = Perft::perft_parallel::<true, false, false>(&fen, depth, threads);
+            let (plain, _) = Perft::perft_parallel::<false, false, false>(&fen, depth, threads);
+            assert_eq!(bulk, expected, "{fen}");
+            assert_eq!(plain, expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn parallel_divide_matches_single_threaded() {
+        for (fen, depth, threads) in CASES {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            let divide = Perft::perft_parallel_divide::<true>(&board, depth, threads);
+            assert!(divide.iter().map(|(mv, _)| *mv).eq(board.get_legal_moves()));
+
+            for (mv, result) in divide {
+                let mut board_copy = board;
+                board_copy.apply_move(mv);
+                let (expected, _) =
+                    Perft::perft::<false, false, false>(&board_copy.get_fen(), depth - 1);
+                assert_eq!(result, expected, "{fen} {mv}");
+            }
+        }
+    }
+
+    #[test]
+    fn depth_zero_counts_the_root() {
+        let fen = FEN::start_position();
+        assert_eq!(Perft::perft_parallel::<true, false, false>(&fen, 0, 4).0, 1);
+        let board = ChessBoard::from_fen(&fen);
+        assert!(Perft::perft_parallel_divide::<true>(&board, 0, 4).is_empty());
+    }
+}
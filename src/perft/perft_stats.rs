@@ -0,0 +1,196 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::{base_structures::Side, ChessBoard, Perft, Square, FEN};
+
+//Move statistics of a single perft depth, matching the columns of the chessprogramming wiki
+//perft tables. Discovered and double checks are also counted as checks, while double checks are
+//not counted as discovered checks.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+impl Perft {
+    //Returns statistics for every depth from 1 to depth, all gathered in a single tree walk
+    pub fn perft_stats<const PRINT: bool>(fen: &FEN, depth: u8) -> Vec<PerftStats> {
+        let board = ChessBoard::from_fen(fen);
+        let mut stats = vec![PerftStats::default(); depth as usize];
+
+        if depth > 0 {
+            if board.side_to_move() == Side::WHITE {
+                perft_stats_internal::<true, false>(&board, depth, 0, &mut stats);
+            } else {
+                perft_stats_internal::<false, true>(&board, depth, 0, &mut stats);
+            }
+        }
+
+        if PRINT {
+            board.draw_board();
+            println!("{}", PerftStats::HEADER);
+            for (index, depth_stats) in stats.iter().enumerate() {
+                println!("{:>5} {depth_stats}", index + 1);
+            }
+        }
+
+        stats
+    }
+}
+
+impl PerftStats {
+    pub const HEADER: &'static str = "Depth         Nodes    Captures   E.p.   Castles Promotions      Checks  Disc.Checks Dbl.Checks Checkmates";
+}
+
+fn perft_stats_internal<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+    board: &ChessBoard,
+    depth: u8,
+    ply: usize,
+    stats: &mut [PerftStats],
+) {
+    board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| {
+        let mut board_copy = *board;
+        board_copy.make_move::<STM_WHITE, NSTM_WHITE>(mv);
+
+        let depth_stats = &mut stats[ply];
+        depth_stats.nodes += 1;
+        depth_stats.captures += u64::from(mv.is_capture());
+        depth_stats.en_passants += u64::from(mv.is_en_passant());
+        depth_stats.castles += u64::from(mv.is_castle());
+        depth_stats.promotions += u64::from(mv.is_promotion());
+
+        let checkers = board_copy.generate_checkers_mask::<NSTM_WHITE, STM_WHITE>();
+        if checkers.is_not_empty() {
+            let moved_piece_square = if mv.is_castle() {
+                rook_castle_destination(mv.get_to_square())
+            } else {
+                mv.get_to_square()
+            };
+
+            depth_stats.checks += 1;
+            if checkers.multiple_one_bits() {
+                depth_stats.double_checks += 1;
+            } else if checkers.exclude(moved_piece_square).is_not_empty() {
+                depth_stats.discovered_checks += 1;
+            }
+
+            let mut has_moves = false;
+            board_copy.map_moves::<_, NSTM_WHITE, STM_WHITE>(|_| has_moves = true);
+            depth_stats.checkmates += u64::from(!has_moves);
+        }
+
+        if depth > 1 {
+            perft_stats_internal::<NSTM_WHITE, STM_WHITE>(&board_copy, depth - 1, ply + 1, stats);
+        }
+    });
+}
+
+fn rook_castle_destination(king_destination: Square) -> Square {
+    if king_destination.get_file() == 6 {
+        king_destination - 1
+    } else {
+        king_destination + 1
+    }
+}
+
+impl Display for PerftStats {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        write!(
+            formatter,
+            "{:>13} {:>11} {:>6} {:>9} {:>10} {:>11} {:>12} {:>10} {:>10}",
+            self.nodes,
+            self.captures,
+            self.en_passants,
+            self.castles,
+            self.promotions,
+            self.checks,
+            self.discovered_checks,
+            self.double_checks,
+            self.checkmates
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PerftStats;
+    use crate::{Perft, FEN};
+
+    fn stats(values: [u64; 9]) -> PerftStats {
+        PerftStats {
+            nodes: values[0],
+            captures: values[1],
+            en_passants: values[2],
+            castles: values[3],
+            promotions: values[4],
+            checks: values[5],
+            discovered_checks: values[6],
+            double_checks: values[7],
+            checkmates: values[8],
+        }
+    }
+
+    fn assert_stats(fen: &str, expected: &[[u64; 9]]) {
+        let result = Perft::perft_stats::<false>(&FEN::from_str(fen), expected.len() as u8);
+        let expected: Vec<PerftStats> = expected.iter().copied().map(stats).collect();
+        assert_eq!(result, expected, "{fen}");
+    }
+
+    //Reference values from the chessprogramming wiki perft results page
+    #[test]
+    fn start_position_matches_reference() {
+        assert_stats(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[
+                [20, 0, 0, 0, 0, 0, 0, 0, 0],
+                [400, 0, 0, 0, 0, 0, 0, 0, 0],
+                [8902, 34, 0, 0, 0, 12, 0, 0, 0],
+                [197281, 1576, 0, 0, 0, 469, 0, 0, 8],
+            ],
+        );
+    }
+
+    #[test]
+    fn kiwipete_matches_reference() {
+        assert_stats(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[
+                [48, 8, 0, 2, 0, 0, 0, 0, 0],
+                [2039, 351, 1, 91, 0, 3, 0, 0, 0],
+                [97862, 17102, 45, 3162, 0, 993, 0, 0, 1],
+            ],
+        );
+    }
+
+    #[test]
+    fn rook_endgame_matches_reference() {
+        assert_stats(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[
+                [14, 1, 0, 0, 0, 2, 0, 0, 0],
+                [191, 14, 0, 0, 0, 10, 0, 0, 0],
+                [2812, 209, 2, 0, 0, 267, 3, 0, 0],
+                [43238, 3348, 123, 0, 0, 1680, 106, 0, 17],
+                [674624, 52051, 1165, 0, 0, 52950, 1292, 3, 0],
+            ],
+        );
+    }
+
+    #[test]
+    fn castling_rook_check_is_not_discovered() {
+        assert_stats(
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            &[[15, 0, 0, 1, 0, 3, 0, 0, 0]],
+        );
+        assert_stats(
+            "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1",
+            &[[16, 0, 0, 1, 0, 3, 0, 0, 0]],
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs, io,
+    path::Path,
+    time::Instant,
+};
+
+use crate::{ChessBoard, Epd, Perft, StringUtils, FEN};
+
+use super::perft_tests::perft_board;
+
+//Perft suite in the EPD layout used by perftsuite.epd and similar collections:
+//<fen> ;D1 20 ;D2 400 ;D3 8902
+#[derive(Clone, Default)]
+pub struct PerftSuite {
+    entries: Vec<PerftSuiteEntry>,
+}
+
+#[derive(Clone)]
+pub struct PerftSuiteEntry {
+    pub fen: FEN,
+    pub expected: Vec<(u8, u128)>,
+}
+
+#[derive(Clone)]
+pub struct PerftSuiteResult {
+    pub index: usize,
+    pub fen: FEN,
+    pub depths: Vec<PerftDepthResult>,
+}
+
+#[derive(Clone, Copy)]
+pub struct PerftDepthResult {
+    pub depth: u8,
+    pub expected: u128,
+    pub nodes: u128,
+    pub duration_us: u128,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct PerftSuiteSummary {
+    pub positions: usize,
+    pub failed: usize,
+    pub nodes: u128,
+    pub duration_us: u128,
+}
+
+impl PerftSuite {
+    pub fn from_epd(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let epd =
+                Epd::from_str(line).map_err(|error| format!("line {}: {error}", line_index + 1))?;
+            let expected = epd.perft_depths();
+            if expected.is_empty() {
+                return Err(format!("line {}: no depth operations", line_index + 1));
+            }
+
+            entries.push(PerftSuiteEntry {
+                fen: epd.fen,
+                expected,
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_epd(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[PerftSuiteEntry] {
+        &self.entries
+    }
+
+    //Runs every position at every listed depth not deeper than max_depth.
+    //The callback receives each position result as soon as it is finished.
+    pub fn run<F: FnMut(&PerftSuiteResult)>(
+        &self,
+        max_depth: u8,
+        threads: usize,
+        mut method: F,
+    ) -> Vec<PerftSuiteResult> {
+        let mut results = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let board = ChessBoard::from_fen(&entry.fen);
+            let mut depths = Vec::new();
+            for &(depth, expected) in entry
+                .expected
+                .iter()
+                .filter(|(depth, _)| *depth <= max_depth)
+            {
+                let timer = Instant::now();
+                let nodes = if depth == 0 {
+                    1
+                } else if threads > 1 {
+                    Perft::perft_parallel_divide::<true>(&board, depth, threads)
+                        .iter()
+                        .map(|(_, nodes)| nodes)
+                        .sum()
+                } else {
+                    perft_board::<true>(&board, depth)
+                };
+                depths.push(PerftDepthResult {
+                    depth,
+                    expected,
+                    nodes,
+                    duration_us: timer.elapsed().as_micros(),
+                });
+            }
+
+            let result = PerftSuiteResult {
+                index,
+                fen: entry.fen.clone(),
+                depths,
+            };
+            method(&result);
+            results.push(result);
+        }
+
+        results
+    }
+
+    //Runs the suite and prints every position result followed by the summary, either as plain
+    //text or as one JSON object per line. Shared by `spear suite` and the perft_suite binary.
+    pub fn run_and_print(&self, max_depth: u8, threads: usize, json: bool) -> PerftSuiteSummary {
+        let results = self.run(max_depth, threads, |result| {
+            if json {
+                println!("{}", result.to_json());
+            } else {
+                println!("{result}");
+            }
+        });
+
+        let summary = PerftSuiteSummary::from_results(&results);
+        if json {
+            println!("{}", summary.to_json());
+        } else {
+            println!("{summary}");
+        }
+        summary
+    }
+}
+
+impl PerftSuiteResult {
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.depths.iter().all(PerftDepthResult::passed)
+    }
+
+    pub fn to_json(&self) -> String {
+        let depths: Vec<String> = self.depths.iter().map(PerftDepthResult::to_json).collect();
+        format!(
+            "{{\"index\":{},\"fen\":{},\"passed\":{},\"depths\":[{}]}}",
+            self.index,
+            StringUtils::json_escape(&self.fen.to_string()),
+            self.passed(),
+            depths.join(",")
+        )
+    }
+}
+
+impl PerftDepthResult {
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.nodes == self.expected
+    }
+
+    #[inline]
+    pub fn nodes_per_second(&self) -> u128 {
+        self.nodes * 1_000_000 / self.duration_us.max(1)
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"depth\":{},\"expected\":{},\"nodes\":{},\"passed\":{},\"time_us\":{},\"nps\":{}}}",
+            self.depth,
+            self.expected,
+            self.nodes,
+            self.passed(),
+            self.duration_us,
+            self.nodes_per_second()
+        )
+    }
+}
+
+impl PerftSuiteSummary {
+    pub fn from_results(results: &[PerftSuiteResult]) -> Self {
+        let mut summary = Self {
+            positions: results.len(),
+            ..Default::default()
+        };
+        for result in results {
+            summary.failed += usize::from(!result.passed());
+            for depth in &result.depths {
+                summary.nodes += depth.nodes;
+                summary.duration_us += depth.duration_us;
+            }
+        }
+        summary
+    }
+
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"positions\":{},\"failed\":{},\"nodes\":{},\"time_us\":{}}}",
+            self.positions, self.failed, self.nodes, self.duration_us
+        )
+    }
+}
+
+impl Display for PerftSuiteResult {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        for (index, depth) in self.depths.iter().enumerate() {
+            if index > 0 {
+                writeln!(formatter)?;
+            }
+            let status = if depth.passed() { "passed" } else { "FAILED" };
+            write!(
+                formatter,
+                "Position {} depth {} {status}: {} nodes (expected {}) in {} ({}nps)",
+                self.index,
+                depth.depth,
+                depth.nodes,
+                depth.expected,
+                StringUtils::time_to_string(depth.duration_us / 1000),
+                StringUtils::large_number_to_string(depth.nodes_per_second())
+            )?;
+        }
+        if !self.passed() {
+            write!(formatter, "\n  {}", self.fen)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for PerftSuiteSummary {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            formatter,
+            "{} of {} positions passed, {} nodes in {}",
+            self.positions - self.failed,
+            self.positions,
+            self.nodes,
+            StringUtils::time_to_string(self.duration_us / 1000)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PerftSuite;
+
+    const SUITE: &str =
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D0 1 ;D1 20 ;D3 8902
+r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ;D0 1 ;D2 2039";
+
+    #[test]
+    fn threaded_runs_match_single_threaded_runs() {
+        let suite = PerftSuite::from_epd(SUITE).unwrap();
+        for threads in [1, 4] {
+            let results = suite.run(3, threads, |_| ());
+            assert!(
+                results.iter().all(|result| result.passed()),
+                "{threads} threads"
+            );
+            assert_eq!(results[0].depths[0].nodes, 1);
+        }
+    }
+}
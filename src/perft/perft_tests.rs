@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-use crate::{base_structures::Side, ChessBoard, StringUtils, FEN};
+use crate::{base_structures::Side, Attacks, ChessBoard, StringUtils, FEN};
 
 pub struct Perft;
 impl Perft {
@@ -11,26 +11,7 @@ impl Perft {
         let board = ChessBoard::from_fen(fen);
 
         if PRINT {
-            let pext = {
-                #[cfg(target_feature = "bmi2")]
-                {
-                    true
-                }
-
-                #[cfg(not(target_feature = "bmi2"))]
-                {
-                    false
-                }
-            };
-
-            board.draw_board();
-            println!("-----------------------------------------------------------");
-            println!("  Starting PERFT");
-            println!("  Depth: {depth}");
-            println!("  Split: {SPLIT}");
-            println!("  Bulk: {BULK}");
-            println!("  PEXT: {pext}",);
-            println!("-----------------------------------------------------------");
+            print_perft_header(&board, depth, SPLIT, BULK, 1);
         }
 
         let timer = Instant::now();
@@ -42,23 +23,43 @@ impl Perft {
         let duration = timer.elapsed().as_millis();
 
         if PRINT {
-            println!("-----------------------------------------------------------");
-            println!(
-                "  Perft ended! {} nodes, {}, {}n/s",
-                result,
-                StringUtils::time_to_string(duration),
-                StringUtils::large_number_to_string(
-                    ((result * 1000) as f64 / duration as f64) as u128
-                )
-            );
-            println!("-----------------------------------------------------------");
+            print_perft_footer(result, duration);
         }
 
         (result, duration)
     }
 }
 
-fn perft_internal<
+pub(super) fn print_perft_header(
+    board: &ChessBoard,
+    depth: u8,
+    split: bool,
+    bulk: bool,
+    threads: usize,
+) {
+    board.draw_board();
+    println!("-----------------------------------------------------------");
+    println!("  Starting PERFT");
+    println!("  Depth: {depth}");
+    println!("  Split: {split}");
+    println!("  Bulk: {bulk}");
+    println!("  Sliders: {}", Attacks::SLIDER_BACKEND);
+    println!("  Threads: {threads}");
+    println!("-----------------------------------------------------------");
+}
+
+pub(super) fn print_perft_footer(result: u128, duration: u128) {
+    println!("-----------------------------------------------------------");
+    println!(
+        "  Perft ended! {} nodes, {}, {}n/s",
+        result,
+        StringUtils::time_to_string(duration),
+        StringUtils::large_number_to_string(StringUtils::nodes_per_second(result, duration))
+    );
+    println!("-----------------------------------------------------------");
+}
+
+pub(super) fn perft_internal<
     const BULK: bool,
     const SPLIT: bool,
     const PRINT: bool,
@@ -71,6 +72,10 @@ fn perft_internal<
 ) -> u128 {
     let mut node_count = 0u128;
 
+    if depth == 0 {
+        return 1;
+    }
+
     if BULK && depth == 1 {
         board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|_| {
             node_count += 1;
@@ -78,10 +83,6 @@ fn perft_internal<
         return node_count;
     }
 
-    if !BULK && depth == 0 {
-        return 1;
-    }
-
     board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| {
         let mut board_copy = *board;
         board_copy.make_move::<STM_WHITE, NSTM_WHITE>(mv);
@@ -98,3 +99,28 @@ fn perft_internal<
 
     node_count
 }
+
+pub(super) fn perft_board<const BULK: bool>(board: &ChessBoard, depth: u8) -> u128 {
+    if depth == 0 {
+        return 1;
+    }
+
+    if board.side_to_move() == Side::WHITE {
+        perft_internal::<BULK, false, false, false, true, false>(board, depth)
+    } else {
+        perft_internal::<BULK, false, false, false, false, true>(board, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Perft, FEN};
+
+    #[test]
+    fn depth_zero_counts_the_root() {
+        let fen = FEN::start_position();
+        assert_eq!(Perft::perft::<true, false, false>(&fen, 0).0, 1);
+        assert_eq!(Perft::perft::<false, true, false>(&fen, 0).0, 1);
+        assert_eq!(Perft::perft_parallel::<true, false, false>(&fen, 0, 4).0, 1);
+    }
+}
@@ -0,0 +1,3 @@
+mod policy_index;
+
+pub use policy_index::PolicyLayout;
@@ -0,0 +1,337 @@
+use crate::{ChessBoard, Move, MoveFlag, Piece, Side, Square};
+
+//Dense policy head layouts. Moves are always indexed from the side to move's point of view,
+//for black to move both squares are flipped vertically, so black pawns promote on "rank 8" too.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PolicyLayout {
+    //from * 64 + to for every move, queen promotions included, then 72 underpromotion slots:
+    //4096 + (from file * 3 + to file - from file + 1) * 3 + knight/bishop/rook.
+    //Castling uses the king's destination square.
+    FromTo,
+    //Lc0 layout: the 1792 queen and knight moves sorted by from and to square, then the
+    //queen/rook/bishop promotions of the 22 promotion moves. Knight promotions share the index
+    //of the plain move and castling is encoded as the king moving onto its rook.
+    Lc0,
+}
+
+const FROM_TO_SIZE: usize = 64 * 64 + 8 * 3 * 3;
+const LC0_SIZE: usize = 1858;
+const NO_INDEX: u16 = u16::MAX;
+
+//(from, to, promotion piece or Piece::NONE) of every Lc0 policy index
+static LC0_MOVES: [(u8, u8, u8); LC0_SIZE] = {
+    let mut result = [(0u8, 0u8, 0u8); LC0_SIZE];
+    let mut count = 0;
+    let mut from: u8 = 0;
+    while from < 64 {
+        let mut to: u8 = 0;
+        while to < 64 {
+            let file_distance = (from % 8).abs_diff(to % 8);
+            let rank_distance = (from / 8).abs_diff(to / 8);
+            let queen_move = from != to
+                && (file_distance == 0 || rank_distance == 0 || file_distance == rank_distance);
+            let knight_move = (file_distance == 1 && rank_distance == 2)
+                || (file_distance == 2 && rank_distance == 1);
+            if queen_move || knight_move {
+                result[count] = (from, to, Piece::NONE.get_raw());
+                count += 1;
+            }
+            to += 1;
+        }
+        from += 1;
+    }
+
+    let promotions = [Piece::QUEEN, Piece::ROOK, Piece::BISHOP];
+    let mut from: u8 = 48;
+    while from < 56 {
+        let mut to: u8 = 56;
+        while to < 64 {
+            if (from % 8).abs_diff(to % 8) <= 1 {
+                let mut promotion_index = 0;
+                while promotion_index < 3 {
+                    result[count] = (from, to, promotions[promotion_index].get_raw());
+                    count += 1;
+                    promotion_index += 1;
+                }
+            }
+            to += 1;
+        }
+        from += 1;
+    }
+
+    assert!(count == LC0_SIZE);
+    result
+};
+
+//Lc0 index by promotion slot (none or knight, queen, rook, bishop) * 4096 + from * 64 + to
+static LC0_LOOKUP: [u16; 4 * 4096] = {
+    let mut result = [NO_INDEX; 4 * 4096];
+    let mut index = 0;
+    while index < LC0_SIZE {
+        let (from, to, promotion) = LC0_MOVES[index];
+        result[lc0_promotion_slot(promotion) * 4096 + from as usize * 64 + to as usize] =
+            index as u16;
+        index += 1;
+    }
+    result
+};
+
+const fn lc0_promotion_slot(promotion: u8) -> usize {
+    if promotion == Piece::QUEEN.get_raw() {
+        1
+    } else if promotion == Piece::ROOK.get_raw() {
+        2
+    } else if promotion == Piece::BISHOP.get_raw() {
+        3
+    } else {
+        0
+    }
+}
+
+impl PolicyLayout {
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            Self::FromTo => FROM_TO_SIZE,
+            Self::Lc0 => LC0_SIZE,
+        }
+    }
+
+    //Index of the move for the given side to move, None when the layout has no slot for it
+    pub fn move_to_index(self, mv: Move, side_to_move: Side) -> Option<usize> {
+        let (from, to) = relative_squares(mv, side_to_move);
+        let promotion = if mv.is_promotion() {
+            mv.get_promotion_piece()
+        } else {
+            Piece::NONE
+        };
+
+        match self {
+            Self::FromTo => {
+                if promotion == Piece::NONE || promotion == Piece::QUEEN {
+                    return Some(from.get_raw() as usize * 64 + to.get_raw() as usize);
+                }
+                if from.get_rank() != 6 || to.get_rank() != 7 {
+                    return None;
+                }
+
+                let direction = (to.get_file() + 1).checked_sub(from.get_file())?;
+                if direction > 2 {
+                    return None;
+                }
+                Some(
+                    4096 + (from.get_file() as usize * 3 + direction as usize) * 3
+                        + (promotion.get_raw() - Piece::KNIGHT.get_raw()) as usize,
+                )
+            }
+            Self::Lc0 => {
+                let to = if mv.is_castle() {
+                    let rook_file = if mv.get_flag() == MoveFlag::KING_SIDE_CASTLE {
+                        7
+                    } else {
+                        0
+                    };
+                    Square::from_coords(to.get_rank(), rook_file)
+                } else {
+                    to
+                };
+                let index = LC0_LOOKUP[lc0_promotion_slot(promotion.get_raw()) * 4096
+                    + from.get_raw() as usize * 64
+                    + to.get_raw() as usize];
+                (index != NO_INDEX).then_some(index as usize)
+            }
+        }
+    }
+
+    //Squares and promotion piece (Piece::NONE for none) of an index, already flipped back to
+    //the board of the given side to move. Knight promotions are reported without promotion
+    //in the Lc0 layout, since they share the index of the plain move.
+    pub fn index_to_squares(
+        self,
+        index: usize,
+        side_to_move: Side,
+    ) -> Option<(Square, Square, Piece)> {
+        let (from, to, promotion) = match self {
+            Self::FromTo if index < 4096 => (index / 64, index % 64, Piece::NONE),
+            Self::FromTo if index < FROM_TO_SIZE => {
+                let slot = index - 4096;
+                let from_file = slot / 9;
+                let to_file = (from_file + (slot / 3) % 3).checked_sub(1)?;
+                if to_file > 7 {
+                    return None;
+                }
+                (
+                    48 + from_file,
+                    56 + to_file,
+                    Piece::from_raw(Piece::KNIGHT.get_raw() + (slot % 3) as u8),
+                )
+            }
+            Self::Lc0 if index < LC0_SIZE => {
+                let (from, to, promotion) = LC0_MOVES[index];
+                (from as usize, to as usize, Piece::from_raw(promotion))
+            }
+            _ => return None,
+        };
+
+        let flip = |square: usize| {
+            let square = Square::from_raw(square as u8);
+            if side_to_move == Side::BLACK {
+                square.flip()
+            } else {
+                square
+            }
+        };
+        Some((flip(from), flip(to), promotion))
+    }
+
+    //Legal move of the board with the given index
+    pub fn index_to_move(self, index: usize, board: &ChessBoard) -> Option<Move> {
+        let mut result = None;
+        board.map_legal_moves(|mv| {
+            if result.is_none() && self.move_to_index(mv, board.side_to_move()) == Some(index) {
+                result = Some(mv)
+            }
+        });
+        result
+    }
+}
+
+impl ChessBoard {
+    //True for the policy indices of all legal moves
+    pub fn legal_policy_mask(&self, layout: PolicyLayout) -> Vec<bool> {
+        let mut mask = vec![false; layout.size()];
+        self.map_legal_moves(|mv| {
+            if let Some(index) = layout.move_to_index(mv, self.side_to_move()) {
+                mask[index] = true
+            }
+        });
+        mask
+    }
+
+    #[inline]
+    pub fn policy_index(&self, mv: Move, layout: PolicyLayout) -> Option<usize> {
+        layout.move_to_index(mv, self.side_to_move())
+    }
+}
+
+//Squares from the side to move's point of view
+fn relative_squares(mv: Move, side_to_move: Side) -> (Square, Square) {
+    let from = mv.get_from_square();
+    let to = mv.get_to_square();
+    if side_to_move == Side::BLACK {
+        (from.flip(), to.flip())
+    } else {
+        (from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PolicyLayout;
+    use crate::{ChessBoard, Piece, Side, Square, FEN};
+
+    const LAYOUTS: [PolicyLayout; 2] = [PolicyLayout::FromTo, PolicyLayout::Lc0];
+
+    const FENS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ];
+
+    #[test]
+    fn move_to_index_round_trips() {
+        for layout in LAYOUTS {
+            for fen in FENS {
+                let board = ChessBoard::from_fen(&FEN::from_str(fen));
+                let mut indices = Vec::new();
+                board.map_legal_moves(|mv| {
+                    let index = board.policy_index(mv, layout).unwrap();
+                    assert!(index < layout.size());
+                    assert!(
+                        layout.index_to_move(index, &board) == Some(mv),
+                        "{fen} {mv}"
+                    );
+
+                    let (from, to, _) = layout
+                        .index_to_squares(index, board.side_to_move())
+                        .unwrap();
+                    assert!(from == mv.get_from_square(), "{fen} {mv}");
+                    if !mv.is_castle() || layout == PolicyLayout::FromTo {
+                        assert!(to == mv.get_to_square(), "{fen} {mv}");
+                    }
+                    indices.push(index);
+                });
+
+                let move_count = indices.len();
+                indices.sort_unstable();
+                indices.dedup();
+                assert_eq!(indices.len(), move_count, "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn pinned_lc0_indices() {
+        let squares = |index| {
+            PolicyLayout::Lc0
+                .index_to_squares(index, Side::WHITE)
+                .unwrap()
+        };
+        let a7 = Square::from_string("a7");
+        let a8 = Square::from_string("a8");
+
+        assert_eq!(PolicyLayout::Lc0.size(), 1858);
+        assert!(
+            squares(0)
+                == (
+                    Square::from_string("a1"),
+                    Square::from_string("b1"),
+                    Piece::NONE
+                )
+        );
+        assert!(squares(1792) == (a7, a8, Piece::QUEEN));
+        assert!(squares(1793) == (a7, a8, Piece::ROOK));
+        assert!(squares(1794) == (a7, a8, Piece::BISHOP));
+        assert!(
+            squares(1857)
+                == (
+                    Square::from_string("h7"),
+                    Square::from_string("h8"),
+                    Piece::BISHOP
+                )
+        );
+        assert!(PolicyLayout::Lc0
+            .index_to_squares(1858, Side::WHITE)
+            .is_none());
+
+        let board = ChessBoard::from_fen(&FEN::start_position());
+        let e2e4 = board.parse_uci_move("e2e4").unwrap();
+        assert_eq!(board.policy_index(e2e4, PolicyLayout::Lc0), Some(322));
+        assert_eq!(board.policy_index(e2e4, PolicyLayout::FromTo), Some(796));
+
+        let board = ChessBoard::from_fen(&FEN::from_str(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        ));
+        let e7e5 = board.parse_uci_move("e7e5").unwrap();
+        assert_eq!(board.policy_index(e7e5, PolicyLayout::Lc0), Some(322));
+
+        let board = ChessBoard::from_fen(&FEN::from_str("4k3/P7/8/8/8/8/8/4K3 w - - 0 1"));
+        let a7a8n = board.parse_uci_move("a7a8n").unwrap();
+        assert_eq!(board.policy_index(a7a8n, PolicyLayout::FromTo), Some(4099));
+    }
+
+    #[test]
+    fn legal_policy_mask_counts_legal_moves() {
+        for (fen, move_count) in [(FENS[0], 20), (FENS[1], 48), (FENS[3], 14)] {
+            let board = ChessBoard::from_fen(&FEN::from_str(fen));
+            for layout in LAYOUTS {
+                let mask = board.legal_policy_mask(layout);
+                assert_eq!(mask.len(), layout.size());
+                assert_eq!(mask.iter().filter(|&&legal| legal).count(), move_count);
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+mod random;
 mod string_utils;
 
+pub use random::Random;
 pub use string_utils::StringUtils;
@@ -0,0 +1,49 @@
+//Small deterministic xorshift64* generator, seeded through splitmix64 so that
+//nearby seeds produce unrelated streams.
+#[derive(Clone, Copy)]
+pub struct Random(u64);
+impl Random {
+    pub fn from_seed(seed: u64) -> Self {
+        let mut value = seed.wrapping_add(0x9E3779B97F4A7C15);
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+        value ^= value >> 31;
+        Self(if value == 0 {
+            0x9E3779B97F4A7C15
+        } else {
+            value
+        })
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    #[inline]
+    pub fn next_sparse_u64(&mut self) -> u64 {
+        self.next_u64() & self.next_u64() & self.next_u64()
+    }
+
+    //Uniform value in 0..max, max has to be greater than 0
+    #[inline]
+    pub fn next_range(&mut self, max: u64) -> u64 {
+        ((self.next_u64() as u128 * max as u128) >> 64) as u64
+    }
+
+    //Uniform value in 0.0..1.0
+    #[inline]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for index in (1..values.len()).rev() {
+            let other = self.next_range(index as u64 + 1) as usize;
+            values.swap(index, other);
+        }
+    }
+}
@@ -1,10 +1,10 @@
 pub struct StringUtils;
 impl StringUtils {
-    pub fn time_to_string(miliseconds: u128) -> String {
-        if miliseconds < 1000 {
-            format!("{miliseconds}ms")
+    pub fn time_to_string(milliseconds: u128) -> String {
+        if milliseconds < 1000 {
+            format!("{milliseconds}ms")
         } else {
-            format!("{:.2}s", miliseconds as f64 / 1000.0)
+            format!("{:.2}s", milliseconds as f64 / 1000.0)
         }
     }
 
@@ -15,4 +15,29 @@ impl StringUtils {
             1_000_000.. => format!("{:.2}M", number as f64 / 1_000_000.0),
         }
     }
+
+    //Durations below one millisecond are treated as one millisecond
+    pub fn nodes_per_second(nodes: u128, milliseconds: u128) -> u128 {
+        nodes * 1000 / milliseconds.max(1)
+    }
+
+    pub fn json_escape(value: &str) -> String {
+        let mut result = String::with_capacity(value.len() + 2);
+        result.push('"');
+        for character in value.chars() {
+            match character {
+                '"' => result.push_str("\\\""),
+                '\\' => result.push_str("\\\\"),
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                character if (character as u32) < 0x20 => {
+                    result.push_str(&format!("\\u{:04x}", character as u32))
+                }
+                character => result.push(character),
+            }
+        }
+        result.push('"');
+        result
+    }
 }